@@ -0,0 +1,17 @@
+use std::error::Error;
+use std::thread;
+use std::time::Duration;
+use dfr_io_hat::{Channel, DfrIoHat};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut hat = DfrIoHat::open_default(1)?;
+
+    hat.set_pwm_freq(1000)?;
+    hat.enable_pwm(true)?;
+    hat.enable_adc(true)?;
+
+    loop {
+        hat.auto_dim(Channel::Ch0, Channel::Ch1, 512, 0.5)?;
+        thread::sleep(Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,13 @@
+use std::error::Error;
+use std::time::Duration;
+use dfr_io_hat::{Channel, DfrIoHat};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut hat = DfrIoHat::open_default(1)?;
+
+    hat.set_pwm_freq(50)?;
+    hat.enable_pwm(true)?;
+    hat.sweep_servo(Channel::Ch0, 0.0, 180.0, Duration::from_secs(2), 5)?;
+
+    Ok(())
+}
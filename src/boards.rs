@@ -0,0 +1,61 @@
+use std::io::Error as IoError;
+
+use crate::bus::Bus;
+use crate::{adc_from_bytes, expect_len, scan_handle, Channel, DfrError, DfrIoHat, I2cHandle, Register, ADC_FULL_SCALE};
+
+/// Every board on a single I2C bus, driven through one handle by switching its slave address between transactions.
+///
+/// Unlike [`DfrIoHat`], this doesn't initialize or reset the boards: it only talks to them.
+pub struct DfrIoHatBus {
+    dev: I2cHandle,
+    addrs: Vec<u8>,
+}
+
+impl DfrIoHatBus {
+    /// Open the given I2C bus and [`scan`](DfrIoHat::scan) it for boards.
+    pub fn open(bus: u8) -> Result<DfrIoHatBus, DfrError> {
+        let mut dev = DfrIoHat::open_bus(bus)?;
+        let addrs = scan_handle(&mut dev);
+
+        Ok(DfrIoHatBus { dev, addrs })
+    }
+
+    /// The addresses of the boards found on the bus, in ascending order.
+    pub fn addresses(&self) -> &[u8] {
+        &self.addrs
+    }
+
+    /// Activate or deactivate the ADC subsystem of every board.
+    pub fn enable_adc(&mut self, enable: bool) -> Result<(), IoError> {
+        for &addr in &self.addrs {
+            self.dev.set_slave_address(addr)?;
+            self.dev.write_byte_data(Register::AdcCtrl as u8, enable as u8)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the specified ADC pin of every board, paired with the address of the board, in the order of [`addresses`](Self::addresses).
+    /// Takes one block read per board: switching the slave address is a local ioctl, not a bus transaction.
+    pub fn read_adc_all_boards(&mut self, channel: Channel) -> Result<Vec<(u8, u16)>, IoError> {
+        let reg = match channel {
+            Channel::Ch0 => Register::AdcCh0,
+            Channel::Ch1 => Register::AdcCh1,
+            Channel::Ch2 => Register::AdcCh2,
+            Channel::Ch3 => Register::AdcCh3,
+        };
+
+        let mut readings = Vec::with_capacity(self.addrs.len());
+        for &addr in &self.addrs {
+            self.dev.set_slave_address(addr)?;
+
+            let mut buf = [0u8; 2];
+            let len = self.dev.read_i2c_block_data(reg as u8, &mut buf)?;
+            expect_len(buf.len(), len)?;
+
+            readings.push((addr, adc_from_bytes(buf, ADC_FULL_SCALE)?));
+        }
+
+        Ok(readings)
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{AdcCalibration, BoardInfo, DfrConfig, DfrError, DfrIoHat, I2cHandle, CHANNEL_COUNT, DEFAULT_ADDRESS, DEFAULT_PID, DEFAULT_VID, FREQ_RANGE};
+
+/// Builder for opening a board with non-default settings, created by [`DfrIoHat::builder`].
+#[derive(Clone)]
+pub struct DfrIoHatBuilder {
+    pub(crate) bus: u8,
+    pub(crate) addr: u8,
+    pub(crate) allow_version_mismatch: bool,
+    pub(crate) frequency: Option<u16>,
+    pub(crate) lock: bool,
+    pub(crate) identities: Vec<BoardInfo>,
+    pub(crate) adc_reference_mv: u16,
+    pub(crate) calibration: [AdcCalibration; CHANNEL_COUNT],
+    pub(crate) pwm_enabled_on_open: bool,
+    pub(crate) servo_auto_frequency: bool,
+    pub(crate) minimal: bool,
+}
+
+impl DfrIoHatBuilder {
+    pub(crate) fn new() -> DfrIoHatBuilder {
+        DfrIoHatBuilder {
+            bus: 1,
+            addr: DEFAULT_ADDRESS,
+            allow_version_mismatch: false,
+            frequency: None,
+            lock: false,
+            identities: vec![BoardInfo {
+                pid: DEFAULT_PID,
+                vid: DEFAULT_VID,
+            }],
+            adc_reference_mv: 5000,
+            calibration: [AdcCalibration::default(); CHANNEL_COUNT],
+            pwm_enabled_on_open: false,
+            servo_auto_frequency: true,
+            minimal: false,
+        }
+    }
+
+    /// I2C bus the board is on. Defaults to `1`, the one exposed on the Raspberry Pi header.
+    pub fn bus(mut self, bus: u8) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// I2C address of the board. Defaults to [`DEFAULT_ADDRESS`].
+    pub fn address(mut self, addr: u8) -> Self {
+        self.addr = addr;
+        self
+    }
+
+    /// Open the board even if its firmware version doesn't match that of any accepted identity with the same PID,
+    /// recording the mismatch in [`DfrIoHat::version_warning`] instead of failing with [`DfrError::SoftVersion`].
+    pub fn allow_version_mismatch(mut self, allow: bool) -> Self {
+        self.allow_version_mismatch = allow;
+        self
+    }
+
+    /// PWM frequency to set while initializing the board, within [`FREQ_RANGE`].
+    /// Left to whatever the board is currently running at if unset.
+    pub fn frequency(mut self, freq: u16) -> Self {
+        self.frequency = Some(freq);
+        self
+    }
+
+    /// Set the PWM frequency to [`SERVO_FREQUENCY`](crate::SERVO_FREQUENCY) the first time a servo is driven,
+    /// unless a frequency was set beforehand, here or with [`DfrIoHat::set_pwm_freq`]. On by default.
+    pub fn servo_auto_frequency(mut self, auto: bool) -> Self {
+        self.servo_auto_frequency = auto;
+        self
+    }
+
+    /// Turn off the state the driver keeps behind the scenes, so that every method maps directly to its transactions:
+    /// [`DfrIoHat::enable_pwm`] and [`DfrIoHat::enable_adc`] always write, the PWM frequency is read back from the board
+    /// whenever it's needed, and [`servo_auto_frequency`](Self::servo_auto_frequency) is ignored. Off by default.
+    /// Settings the caller makes explicitly (inversion, duty limits, calibration, failsafe, park duties, batches) still apply.
+    pub fn minimal(mut self, minimal: bool) -> Self {
+        self.minimal = minimal;
+        self
+    }
+
+    /// Hold an advisory lock on `/run/lock/dfr-io-hat-{bus}-{addr}` for as long as the board is open,
+    /// failing with [`DfrError::AlreadyInUse`] if another handle (in this process or another) holds it.
+    /// Only guards against other users of this crate that enable the lock too. Off by default.
+    pub fn lock(mut self, lock: bool) -> Self {
+        self.lock = lock;
+        self
+    }
+
+    /// Leave the PWM subsystem enabled once initialized, with all duty cycles still zeroed, so that the first
+    /// [`DfrIoHat::set_pwm_duty`] takes effect without calling [`DfrIoHat::enable_pwm`]. Off by default.
+    pub fn pwm_enabled_on_open(mut self, enabled: bool) -> Self {
+        self.pwm_enabled_on_open = enabled;
+        self
+    }
+
+    /// The (PID, VID) pairs a board may report to be accepted, e.g. to support several revisions.
+    /// Defaults to the single identity of the supported board, ([`DEFAULT_PID`], [`DEFAULT_VID`]).
+    /// The identity the board matched is available from [`DfrIoHat::identity`] once opened.
+    pub fn accept_identities(mut self, identities: &[(u8, u8)]) -> Self {
+        self.identities = identities.iter()
+            .map(|&(pid, vid)| BoardInfo { pid, vid })
+            .collect();
+        self
+    }
+
+    /// Take the address, PWM frequency, expected identity, ADC reference voltage and calibration from a configuration.
+    pub fn config(mut self, config: &DfrConfig) -> Self {
+        self.addr = config.address;
+        self.frequency = Some(config.frequency);
+        self.identities = vec![BoardInfo {
+            pid: config.pid,
+            vid: config.vid,
+        }];
+        self.adc_reference_mv = (config.adc_reference * 1e3).round() as u16;
+        self.calibration = config.calibration;
+        self
+    }
+
+    /// Open the board with these settings.
+    pub fn open(self) -> Result<DfrIoHat, DfrError> {
+        self.validate()?;
+        let dev = DfrIoHat::open_bus(self.bus)?;
+
+        self.from_i2c(dev)
+    }
+
+    /// Open the board with these settings on an already opened I2C bus handle.
+    /// The configured bus is only used to name the [`lock`](Self::lock) file.
+    pub fn from_i2c(self, dev: I2cHandle) -> Result<DfrIoHat, DfrError> {
+        self.validate()?;
+
+        DfrIoHat::connect(Box::new(dev), self)
+    }
+
+    fn validate(&self) -> Result<(), DfrError> {
+        if let Some(freq) = self.frequency {
+            if !FREQ_RANGE.contains(&freq) {
+                return Err(DfrError::InvalidFrequency(freq));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use std::io::Error as IoError;
+
+/// Handle on the I2C bus the driver talks to the board through.
+/// This is [`i2c_linux::I2c`] by default, or [`linux_embedded_hal::I2cdev`] with the `backend-lehal` feature.
+#[cfg(not(feature = "backend-lehal"))]
+pub type I2cHandle = i2c_linux::I2c<std::fs::File>;
+
+/// Handle on the I2C bus the driver talks to the board through.
+/// This is [`i2c_linux::I2c`] by default, or [`linux_embedded_hal::I2cdev`] with the `backend-lehal` feature.
+#[cfg(feature = "backend-lehal")]
+pub type I2cHandle = linux_embedded_hal::I2cdev;
+
+#[cfg(not(any(feature = "backend-i2c-linux", feature = "backend-lehal")))]
+compile_error!("Enable one of the `backend-i2c-linux` or `backend-lehal` features.");
+
+/// The bus handle a [`DfrIoHat`](crate::DfrIoHat) talks through: a backend's [`I2cHandle`], or a mock in tests.
+pub(crate) type BusHandle = Box<dyn Bus + Send>;
+
+/// The SMBus transactions the driver needs from a backend.
+pub(crate) trait Bus {
+    fn open(path: &str) -> Result<Self, IoError> where Self: Sized;
+
+    fn set_slave_address(&mut self, addr: u8) -> Result<(), IoError>;
+
+    /// Enable or disable SMBus packet error checking on every following transaction.
+    fn set_pec(&mut self, enable: bool) -> Result<(), IoError>;
+
+    /// Whether a device acknowledges its address, with an SMBus quick write: no register is read or written.
+    fn quick_probe(&mut self) -> bool;
+
+    fn read_byte_data(&mut self, reg: u8) -> Result<u8, IoError>;
+
+    /// Read `buf.len()` bytes starting at [`reg`] with an I2C block read, returning how many bytes were read.
+    /// Unlike an SMBus block read, the board sends no count byte: every byte is data.
+    fn read_i2c_block_data(&mut self, reg: u8, buf: &mut [u8]) -> Result<usize, IoError>;
+
+    fn write_byte_data(&mut self, reg: u8, value: u8) -> Result<(), IoError>;
+
+    /// Write [`bytes`] starting at [`reg`] with an I2C block write, which puts no count byte on the wire, unlike an SMBus block write.
+    fn write_i2c_block_data(&mut self, reg: u8, bytes: &[u8]) -> Result<(), IoError>;
+}
+
+#[cfg(not(feature = "backend-lehal"))]
+impl Bus for I2cHandle {
+    fn open(path: &str) -> Result<Self, IoError> {
+        i2c_linux::I2c::from_path(path)
+    }
+
+    fn set_slave_address(&mut self, addr: u8) -> Result<(), IoError> {
+        self.smbus_set_slave_address(addr as u16, false)
+    }
+
+    fn set_pec(&mut self, enable: bool) -> Result<(), IoError> {
+        self.smbus_set_pec(enable)
+    }
+
+    fn quick_probe(&mut self) -> bool {
+        self.smbus_write_quick(i2c_linux::ReadWrite::Write).is_ok()
+    }
+
+    fn read_byte_data(&mut self, reg: u8) -> Result<u8, IoError> {
+        self.smbus_read_byte_data(reg)
+    }
+
+    fn read_i2c_block_data(&mut self, reg: u8, buf: &mut [u8]) -> Result<usize, IoError> {
+        self.i2c_read_block_data(reg, buf)
+    }
+
+    fn write_byte_data(&mut self, reg: u8, value: u8) -> Result<(), IoError> {
+        self.smbus_write_byte_data(reg, value)
+    }
+
+    fn write_i2c_block_data(&mut self, reg: u8, bytes: &[u8]) -> Result<(), IoError> {
+        self.i2c_write_block_data(reg, bytes)
+    }
+}
+
+#[cfg(feature = "backend-lehal")]
+impl Bus for I2cHandle {
+    fn open(path: &str) -> Result<Self, IoError> {
+        Ok(linux_embedded_hal::I2cdev::new(path)?)
+    }
+
+    fn set_slave_address(&mut self, addr: u8) -> Result<(), IoError> {
+        Ok(linux_embedded_hal::i2cdev::linux::LinuxI2CDevice::set_slave_address(self, addr as u16)?)
+    }
+
+    fn set_pec(&mut self, enable: bool) -> Result<(), IoError> {
+        Ok(linux_embedded_hal::i2cdev::linux::LinuxI2CDevice::set_smbus_pec(self, enable)?)
+    }
+
+    fn quick_probe(&mut self) -> bool {
+        use linux_embedded_hal::i2cdev::core::I2CDevice;
+
+        self.smbus_write_quick(false).is_ok()
+    }
+
+    fn read_byte_data(&mut self, reg: u8) -> Result<u8, IoError> {
+        use linux_embedded_hal::i2cdev::core::I2CDevice;
+
+        Ok(self.smbus_read_byte_data(reg)?)
+    }
+
+    fn read_i2c_block_data(&mut self, reg: u8, buf: &mut [u8]) -> Result<usize, IoError> {
+        use linux_embedded_hal::i2cdev::core::I2CDevice;
+
+        let block = self.smbus_read_i2c_block_data(reg, buf.len() as u8)?;
+        let len = block.len().min(buf.len());
+        buf[..len].copy_from_slice(&block[..len]);
+
+        Ok(len)
+    }
+
+    fn write_byte_data(&mut self, reg: u8, value: u8) -> Result<(), IoError> {
+        use linux_embedded_hal::i2cdev::core::I2CDevice;
+
+        Ok(self.smbus_write_byte_data(reg, value)?)
+    }
+
+    fn write_i2c_block_data(&mut self, reg: u8, bytes: &[u8]) -> Result<(), IoError> {
+        use linux_embedded_hal::i2cdev::core::I2CDevice;
+
+        Ok(self.smbus_write_i2c_block_data(reg, bytes)?)
+    }
+}
+
+/// Register file standing in for a board in tests, shared with the test so that it can inspect and tamper with it.
+/// Block transfers are modelled as I2C block transfers, the only kind the driver makes: raw register bytes, no count byte.
+#[cfg(test)]
+#[derive(Clone)]
+pub(crate) struct MockBus {
+    pub(crate) regs: std::sync::Arc<std::sync::Mutex<[u8; 0x100]>>,
+    /// Bytes returned by every block read, at most, to simulate short reads.
+    pub(crate) block_limit: Option<usize>,
+    /// Addresses held by a kernel driver, which can't be selected.
+    pub(crate) busy: Vec<u8>,
+}
+
+#[cfg(test)]
+impl MockBus {
+    /// A board with the identity of the supported one, and every other register zeroed.
+    pub(crate) fn new() -> Self {
+        let mut regs = [0; 0x100];
+        regs[crate::Register::PID as usize] = crate::DEFAULT_PID;
+        regs[crate::Register::VID as usize] = crate::DEFAULT_VID;
+
+        MockBus {
+            regs: std::sync::Arc::new(std::sync::Mutex::new(regs)),
+            block_limit: None,
+            busy: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+impl Bus for MockBus {
+    fn open(_path: &str) -> Result<Self, IoError> {
+        Ok(MockBus::new())
+    }
+
+    fn set_slave_address(&mut self, addr: u8) -> Result<(), IoError> {
+        if self.busy.contains(&addr) {
+            return Err(IoError::from_raw_os_error(16)); // EBUSY
+        }
+
+        Ok(())
+    }
+
+    fn set_pec(&mut self, _enable: bool) -> Result<(), IoError> {
+        Ok(())
+    }
+
+    fn quick_probe(&mut self) -> bool {
+        true
+    }
+
+    fn read_byte_data(&mut self, reg: u8) -> Result<u8, IoError> {
+        Ok(self.regs.lock().unwrap()[reg as usize])
+    }
+
+    fn read_i2c_block_data(&mut self, reg: u8, buf: &mut [u8]) -> Result<usize, IoError> {
+        let len = buf.len().min(self.block_limit.unwrap_or(usize::MAX));
+        let start = reg as usize;
+        buf[..len].copy_from_slice(&self.regs.lock().unwrap()[start..start + len]);
+
+        Ok(len)
+    }
+
+    fn write_byte_data(&mut self, reg: u8, value: u8) -> Result<(), IoError> {
+        self.regs.lock().unwrap()[reg as usize] = value;
+
+        Ok(())
+    }
+
+    fn write_i2c_block_data(&mut self, reg: u8, bytes: &[u8]) -> Result<(), IoError> {
+        let start = reg as usize;
+        self.regs.lock().unwrap()[start..start + bytes.len()].copy_from_slice(bytes);
+
+        Ok(())
+    }
+}
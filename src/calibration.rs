@@ -0,0 +1,58 @@
+use std::fs;
+use std::path::Path;
+
+use crate::{AdcCalibration, DfrError, DfrIoHat, ServoCalibration, CHANNEL_COUNT};
+
+/// Version of the calibration file format written by [`DfrIoHat::save_calibration`].
+const CALIBRATION_VERSION: u32 = 1;
+
+/// Per-channel calibration state, as saved to a file. Arrays are indexed by channel.
+/// Fields added in later versions must default, so that older files keep loading.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CalibrationFile {
+    version: u32,
+    adc: [AdcCalibration; CHANNEL_COUNT],
+    servo: [ServoCalibration; CHANNEL_COUNT],
+    luts: [Vec<(u16, f32)>; CHANNEL_COUNT],
+}
+
+impl DfrIoHat {
+    /// Save the calibration of every channel, the ADC calibration, servo pulse widths and lookup tables, to a JSON file at [`path`].
+    pub fn save_calibration(&self, path: impl AsRef<Path>) -> Result<(), DfrError> {
+        let file = CalibrationFile {
+            version: CALIBRATION_VERSION,
+            adc: self.calibration,
+            servo: self.servo_calibration,
+            luts: self.luts.clone(),
+        };
+
+        let json = serde_json::to_string_pretty(&file).map_err(|err| DfrError::Io(err.into()))?;
+        fs::write(path, json)?;
+
+        Ok(())
+    }
+
+    /// Load the calibration of every channel saved by [`save_calibration`](Self::save_calibration), replacing the current one.
+    /// Fails with [`DfrError::InvalidParameter`], leaving the calibration untouched, if the file is from a newer version of the format
+    /// or holds an invalid servo calibration.
+    pub fn load_calibration(&mut self, path: impl AsRef<Path>) -> Result<(), DfrError> {
+        let json = fs::read_to_string(path)?;
+        let mut file: CalibrationFile = serde_json::from_str(&json).map_err(|err| DfrError::Io(err.into()))?;
+
+        if file.version > CALIBRATION_VERSION {
+            return Err(DfrError::InvalidParameter("calibration file from a newer version of the format"));
+        }
+        if file.servo.iter().any(|servo| servo.min_us >= servo.max_us) {
+            return Err(DfrError::InvalidParameter("servo calibration must have min_us below max_us"));
+        }
+
+        for lut in &mut file.luts {
+            lut.sort_by_key(|&(raw, _)| raw);
+        }
+        self.calibration = file.adc;
+        self.servo_calibration = file.servo;
+        self.luts = file.luts;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{CHANNEL_COUNT, DEFAULT_ADDRESS, DEFAULT_PID, DEFAULT_VID};
+
+/// Complete configuration of a board, applied when opening it with [`DfrIoHatBuilder::config`](crate::DfrIoHatBuilder::config).
+/// Start from [`Default::default`] and override what differs.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DfrConfig {
+    /// I2C address of the board.
+    pub address: u8,
+    /// Voltage a full-scale ADC reading stands for, in volts.
+    pub adc_reference: f32,
+    /// PWM frequency of the board, within [`FREQ_RANGE`](crate::FREQ_RANGE).
+    pub frequency: u16,
+    /// Product ID the board must report.
+    pub pid: u8,
+    /// Firmware version the board must report.
+    pub vid: u8,
+    /// Calibration of each ADC pin, indexed by channel.
+    pub calibration: [AdcCalibration; CHANNEL_COUNT],
+}
+
+/// Linear correction applied to the voltage measured on an ADC pin: `gain * measured + offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdcCalibration {
+    pub gain: f32,
+    /// Offset in volts.
+    pub offset: f32,
+}
+
+impl Default for DfrConfig {
+    /// - `address`: [`DEFAULT_ADDRESS`] (`0x10`), the factory default.
+    /// - `adc_reference`: `5.0` V.
+    /// - `frequency`: `50` Hz, the refresh rate of hobby servos.
+    /// - `pid`/`vid`: [`DEFAULT_PID`] and [`DEFAULT_VID`], the identity of the supported board.
+    /// - `calibration`: no correction on any channel.
+    fn default() -> Self {
+        DfrConfig {
+            address: DEFAULT_ADDRESS,
+            adc_reference: 5.0,
+            frequency: 50,
+            pid: DEFAULT_PID,
+            vid: DEFAULT_VID,
+            calibration: [AdcCalibration::default(); CHANNEL_COUNT],
+        }
+    }
+}
+
+impl Default for AdcCalibration {
+    /// No correction: a gain of `1.0` and no offset.
+    fn default() -> Self {
+        AdcCalibration {
+            gain: 1.0,
+            offset: 0.0,
+        }
+    }
+}
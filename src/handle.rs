@@ -0,0 +1,47 @@
+use std::io::Error as IoError;
+
+use crate::{Channel, DfrError, DfrIoHat};
+
+/// A single channel of a board, borrowed from it by [`DfrIoHat::channel`], to chain per-channel operations fluently.
+pub struct ChannelHandle<'a> {
+    hat: &'a mut DfrIoHat,
+    channel: Channel,
+}
+
+impl DfrIoHat {
+    /// Operate on a single channel, e.g. `hat.channel(Channel::Ch1).set_duty(0.5)?`.
+    pub fn channel(&mut self, channel: Channel) -> ChannelHandle<'_> {
+        ChannelHandle { hat: self, channel }
+    }
+}
+
+impl ChannelHandle<'_> {
+    /// The channel this handle operates on.
+    pub fn id(&self) -> Channel {
+        self.channel
+    }
+
+    /// See [`DfrIoHat::set_pwm_duty`].
+    pub fn set_duty(&mut self, duty: f32) -> Result<&mut Self, IoError> {
+        self.hat.set_pwm_duty(self.channel, duty)?;
+
+        Ok(self)
+    }
+
+    /// See [`DfrIoHat::get_pwm_duty`].
+    pub fn duty(&mut self) -> Result<f32, IoError> {
+        self.hat.get_pwm_duty(self.channel)
+    }
+
+    /// See [`DfrIoHat::get_adc_value`].
+    pub fn adc(&mut self) -> Result<u16, IoError> {
+        self.hat.get_adc_value(self.channel)
+    }
+
+    /// See [`DfrIoHat::set_servo_angle`].
+    pub fn set_servo_angle(&mut self, angle: f32) -> Result<&mut Self, DfrError> {
+        self.hat.set_servo_angle(self.channel, angle)?;
+
+        Ok(self)
+    }
+}
@@ -1,13 +1,99 @@
 use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
-use std::fs::File;
-use std::io::Error as IoError;
-use i2c_linux::I2c;
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Error as IoError, ErrorKind};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::bus::{Bus, BusHandle};
+use crate::watchdog::Watchdog;
+
+pub use crate::boards::DfrIoHatBus;
+pub use crate::builder::DfrIoHatBuilder;
+pub use crate::bus::I2cHandle;
+pub use crate::config::{AdcCalibration, DfrConfig};
+pub use crate::handle::ChannelHandle;
+pub use crate::motor::Motor;
+pub use crate::servo::{ServoCalibration, ServoConfig, SERVO_FREQUENCY, SERVO_RANGE};
+pub use crate::shared::SharedDfrIoHat;
+pub use crate::stepper::{Direction, StepMode, Stepper};
+pub use crate::units::{Duty, Freq};
+
+mod boards;
+mod builder;
+mod bus;
+#[cfg(feature = "serde")]
+mod calibration;
+mod config;
+mod handle;
+mod motor;
+mod servo;
+mod shared;
+mod stepper;
+#[cfg(feature = "tokio")]
+mod stream;
+mod units;
+mod watchdog;
+
+/// Factory-default I2C address of the board.
+pub const DEFAULT_ADDRESS: u8 = 0x10;
+
+/// Product ID reported by the supported board.
+pub const DEFAULT_PID: u8 = 0xDF;
+
+/// Firmware version reported by the supported board.
+pub const DEFAULT_VID: u8 = 0x10;
+
+/// Number of channels on the board, each with a PWM output and an ADC input.
+pub const CHANNEL_COUNT: usize = 4;
+
+/// PWM frequencies the board accepts, in hertz. Taken from the reference library: the firmware doesn't report its limits.
+pub const FREQ_RANGE: RangeInclusive<u16> = 1..=1000;
+
+/// Highest value the 10-bit ADC can report, the default full scale of readings, see [`DfrIoHat::set_adc_bits`].
+pub const ADC_FULL_SCALE: u16 = 1023;
+
+/// Number of register addresses on the board, from `0x00` up: the size of [`DfrIoHat::dump_registers`].
+pub const REGISTER_MAP_LEN: usize = 0x17;
 
 pub struct DfrIoHat {
-    dev: I2c<File>,
+    dev: Arc<Mutex<BusHandle>>,
+    watchdog: Option<Watchdog>,
+    inverted: [bool; CHANNEL_COUNT],
+    duty_limits: [(f32, f32); CHANNEL_COUNT],
+    options: DfrIoHatBuilder,
+    version_warning: Option<BoardInfo>,
+    lock_file: Option<File>,
+    adc_reference_mv: u16,
+    calibration: [AdcCalibration; CHANNEL_COUNT],
+    frequency: Option<u16>,
+    mappings: [(f32, f32, &'static str); CHANNEL_COUNT],
+    identity: BoardInfo,
+    servo_calibration: [ServoCalibration; CHANNEL_COUNT],
+    servo_deadband: [f32; CHANNEL_COUNT],
+    servo_trim: [f32; CHANNEL_COUNT],
+    pwm_enabled: Option<bool>,
+    adc_enabled: Option<bool>,
+    failsafe: Option<[f32; CHANNEL_COUNT]>,
+    luts: [Vec<(u16, f32)>; CHANNEL_COUNT],
+    park_duties: [f32; CHANNEL_COUNT],
+    parked: Option<[f32; CHANNEL_COUNT]>,
+    batch: Option<[Option<f32>; CHANNEL_COUNT]>,
+    adc_full_scale: u16,
+    error_policy: ErrorPolicy,
+    freq_configured: bool,
+    servo_in_use: bool,
+    hook: Option<TransactionHook>,
+    ema: [Option<(f32, Option<f32>)>; CHANNEL_COUNT],
+    /// Whether the board is known to be as [`reset`](Self::reset) leaves it, with nothing written since, or not identified yet: lets [`Drop`] skip resetting it.
+    reset_done: bool,
+    drop_action: DropAction,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Channel {
     Ch0 = 0x00,
     Ch1 = 0x01,
@@ -15,7 +101,50 @@ pub enum Channel {
     Ch3 = 0x03,
 }
 
-#[allow(dead_code)]
+/// Interpolation curve applied to the progress of a fade.
+#[derive(Clone, Copy, Default)]
+pub enum Easing {
+    /// Constant rate of change.
+    #[default]
+    Linear,
+    /// Start slow, finish fast.
+    EaseIn,
+    /// Start fast, finish slow.
+    EaseOut,
+    /// Start and finish slow, fastest in the middle.
+    EaseInOut,
+}
+
+/// How the methods listed in [`DfrIoHat::set_error_policy`] handle failed writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Return the error.
+    #[default]
+    Strict,
+    /// Ignore the error and carry on.
+    BestEffort,
+}
+
+/// What dropping a [`DfrIoHat`] does to the board before letting go of it, see [`DfrIoHat::set_drop_action`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropAction {
+    /// Reset the board as with [`DfrIoHat::reset`].
+    #[default]
+    Reset,
+    /// Fade every channel to off over `duration`, capped to 3 seconds so that dropping
+    /// doesn't hang, then reset the board. Blocks the thread dropping the instance for the duration of the fade.
+    FadeOff { duration: Duration },
+}
+
+/// Longest fade [`DropAction::FadeOff`] blocks a drop for.
+const MAX_DROP_FADE: Duration = Duration::from_secs(3);
+
+/// Register map of the board. Single byte control registers are read and written with SMBus byte-data transfers,
+/// which put the same bytes on the wire as the reference library's 1-byte I2C block transfers; wider registers with I2C block
+/// transfers, as in the reference library, which carry no count byte, unlike SMBus block transfers. See [`Register::transfer_kind`].
+/// This is the whole map of the reference library: there's no status or fault register.
+#[derive(Clone, Copy)]
+#[allow(dead_code, clippy::upper_case_acronyms)]
 enum Register {
     SlaveAddr = 0x00,
     PID = 0x01,
@@ -31,110 +160,634 @@ enum Register {
     AdcCh1 = 0x11,
     AdcCh2 = 0x13,
     AdcCh3 = 0x15,
+}
+
+/// Identity reported by the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardInfo {
+    pub pid: u8,
+    pub vid: u8,
+}
+
+/// Transaction a register is read and written with.
+enum TransferKind {
+    /// SMBus byte-data transfer.
+    Byte,
+    /// I2C block transfer, starting at the register and running on through the following ones: no count byte on the wire.
+    I2cBlock,
+}
+
+/// A transaction with the board, as seen by the hook set with [`DfrIoHat::set_transaction_hook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transaction<'a> {
+    /// Address of the register the transaction starts at.
+    pub register: u8,
+    pub kind: TransactionKind,
+    /// Bytes read or written.
+    pub bytes: &'a [u8],
+}
 
-    DefPID = 0xDF,
-    DefVID = 0x10,
+/// Direction of a [`Transaction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionKind {
+    Read,
+    Write,
 }
 
-enum BoardError {
-    DeviceNotDetected,
+/// Hook set with [`DfrIoHat::set_transaction_hook`]. `Send` so that [`SharedDfrIoHat`] can be shared across threads.
+type TransactionHook = Box<dyn FnMut(Transaction<'_>) + Send>;
+
+/// Point-in-time state of the board, as captured by [`DfrIoHat::snapshot`]. Arrays are indexed by channel.
+///
+/// There's no fault or status to include: the board has no status register (e.g. for overcurrent or overtemperature),
+/// the reference library maps nothing beyond the registers read here and the identity.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardSnapshot {
+    pub pwm_enabled: bool,
+    pub adc_enabled: bool,
+    pub frequency: u16,
+    pub duties: [f32; CHANNEL_COUNT],
+    pub adc_values: [u16; CHANNEL_COUNT],
+}
+
+/// A raw ADC reading, along with the channel it was taken on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    pub channel: Channel,
+    /// Between `0` and the full scale of the ADC.
+    pub raw: u16,
+}
+
+#[derive(Debug)]
+pub enum DfrError {
+    /// Nothing answered the identity read at the address, e.g. because of bad wiring or the wrong address.
+    NoResponse { source: IoError },
+    /// A device answered at the address, but with a product ID that isn't one of [`DfrIoHatBuilder::accept_identities`].
+    WrongDevice { pid: u8, vid: u8 },
     SoftVersion,
+    NotFound,
+    /// A channel index that isn't below [`CHANNEL_COUNT`].
+    InvalidChannel(u8),
+    /// An argument outside of its valid range, described by the message.
+    InvalidParameter(&'static str),
+    /// A PWM frequency outside of [`FREQ_RANGE`].
+    InvalidFrequency(u16),
+    /// A PWM duty cycle outside of `0.0..=1.0`.
+    InvalidDuty(f32),
+    /// The device node of the bus can't be opened by the current user.
+    PermissionDenied { path: PathBuf, source: IoError },
+    /// There's no device node for the bus, e.g. because I2C isn't enabled. See [`DfrIoHat::available_buses`].
+    BusNotFound { bus: u8, path: PathBuf },
+    /// Another handle holds the advisory lock on the board, see [`DfrIoHatBuilder::lock`].
+    AlreadyInUse { path: PathBuf },
+    /// The board didn't answer at its new address, see [`DfrIoHat::change_address_verified`].
+    AddressChangeFailed { requested: u8 },
+    /// A register read back a different value than was just written to it.
+    VerifyMismatch { wrote: u8, read: u8 },
+    /// A block read returned fewer bytes than requested.
+    /// Surfaces from methods returning [`IoError`] wrapped in an [`ErrorKind::UnexpectedEof`] error.
+    ShortRead { expected: u8, got: u8 },
+    /// An ADC reading above the full scale of the ADC, which it can't produce: the read was corrupted on the bus,
+    /// e.g. by a slow device stretching the clock. Surfaces from methods returning [`IoError`] wrapped in an [`ErrorKind::InvalidData`] error.
+    ImplausibleReading { value: u16 },
+    Io(IoError),
 }
 
 impl DfrIoHat {
     /// Open on the factory-default I2C address (0x10) on the given bus.
-    pub fn open_default(bus: u8) -> Result<DfrIoHat, Box<dyn Error>> {
-        Self::open(bus, 0x10)
+    pub fn open_default(bus: u8) -> Result<DfrIoHat, DfrError> {
+        Self::open(bus, DEFAULT_ADDRESS)
     }
 
     /// Open on the given I2C bus and address.
-    pub fn open(bus: u8, addr: u8) -> Result<DfrIoHat, Box<dyn Error>> {
-        let mut dev = I2c::from_path(format!("/dev/i2c-{}", bus))?;
-        dev.smbus_set_slave_address(addr as u16, false)?;
+    pub fn open(bus: u8, addr: u8) -> Result<DfrIoHat, DfrError> {
+        Self::builder().bus(bus).address(addr).open()
+    }
+
+    /// Configure how to open the board, for settings beyond the bus and address.
+    pub fn builder() -> DfrIoHatBuilder {
+        DfrIoHatBuilder::new()
+    }
+
+    /// Open the first board found on the given bus: on the factory-default address if it's there, else on the first address [`scan`] finds.
+    /// Only scans if nothing answers on the default address: any other failure there, e.g. [`DfrError::SoftVersion`], is returned as is.
+    pub fn open_auto(bus: u8) -> Result<DfrIoHat, DfrError> {
+        match Self::open_default(bus) {
+            Err(DfrError::NoResponse { .. }) => {}
+            result => return result,
+        }
+
+        match Self::scan(bus)?.first() {
+            Some(&addr) => Self::open(bus, addr),
+            None => Err(DfrError::NotFound),
+        }
+    }
+
+    /// List the addresses on the given bus at which a board identifies itself, without initializing any of them.
+    pub fn scan(bus: u8) -> Result<Vec<u8>, DfrError> {
+        let mut dev = Self::open_bus(bus)?;
+
+        Ok(scan_handle(&mut dev))
+    }
+
+    /// Take ownership of an already opened I2C bus handle and talk to the board at the given address.
+    /// Useful when the handle has been configured beforehand (e.g. timeouts or retries).
+    pub fn from_i2c(dev: I2cHandle, addr: u8) -> Result<DfrIoHat, DfrError> {
+        Self::builder().address(addr).from_i2c(dev)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "open", skip_all, fields(bus = options.bus, addr = options.addr)))]
+    fn connect(mut dev: BusHandle, options: DfrIoHatBuilder) -> Result<DfrIoHat, DfrError> {
+        let lock_file = if options.lock {
+            Some(Self::acquire_lock(options.bus, options.addr)?)
+        } else {
+            None
+        };
+        dev.set_slave_address(options.addr)?;
 
         let mut hat = DfrIoHat {
-            dev,
+            dev: Arc::new(Mutex::new(dev)),
+            watchdog: None,
+            inverted: [false; CHANNEL_COUNT],
+            duty_limits: [(0f32, 1f32); CHANNEL_COUNT],
+            version_warning: None,
+            lock_file,
+            adc_reference_mv: options.adc_reference_mv,
+            calibration: options.calibration,
+            frequency: None,
+            mappings: [(1f32, 0f32, "V"); CHANNEL_COUNT],
+            identity: BoardInfo { pid: 0, vid: 0 }, // Set by begin
+            servo_calibration: [ServoCalibration::default(); CHANNEL_COUNT],
+            servo_deadband: [0f32; CHANNEL_COUNT],
+            servo_trim: [0f32; CHANNEL_COUNT],
+            pwm_enabled: None,
+            adc_enabled: None,
+            failsafe: None,
+            luts: Default::default(),
+            park_duties: [0f32; CHANNEL_COUNT],
+            parked: None,
+            batch: None,
+            adc_full_scale: ADC_FULL_SCALE,
+            error_policy: ErrorPolicy::Strict,
+            freq_configured: false,
+            servo_in_use: false,
+            hook: None,
+            ema: [None; CHANNEL_COUNT],
+            // Until begin identifies the board: dropping a device that isn't one mustn't write to it.
+            reset_done: true,
+            drop_action: DropAction::Reset,
+            options,
         };
         hat.begin()?;
 
         Ok(hat)
     }
 
+    /// Open the device node of the given bus, explaining the usual fix along with permission errors and missing nodes.
+    pub(crate) fn open_bus(bus: u8) -> Result<I2cHandle, DfrError> {
+        let path = format!("/dev/i2c-{}", bus);
+
+        I2cHandle::open(&path).map_err(|err| match err.kind() {
+            ErrorKind::PermissionDenied => DfrError::PermissionDenied {
+                path: path.into(),
+                source: err,
+            },
+            ErrorKind::NotFound => DfrError::BusNotFound {
+                bus,
+                path: path.into(),
+            },
+            _ => err.into(),
+        })
+    }
+
+    /// List the numbers of the I2C buses with a device node, `/dev/i2c-*`, in ascending order. Empty if `/dev` can't be read.
+    pub fn available_buses() -> Vec<u8> {
+        let Ok(entries) = fs::read_dir("/dev") else {
+            return vec![];
+        };
+
+        let mut buses: Vec<u8> = entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.strip_prefix("i2c-")?.parse().ok())
+            .collect();
+        buses.sort_unstable();
+
+        buses
+    }
+
+    /// Take the advisory lock for the board at the given bus and address. Released when the returned file is closed.
+    fn acquire_lock(bus: u8, addr: u8) -> Result<File, DfrError> {
+        let path = PathBuf::from(format!("/run/lock/dfr-io-hat-{}-{}", bus, addr));
+        let file = OpenOptions::new().create(true).truncate(false).write(true).open(&path)?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(file),
+            Err(TryLockError::WouldBlock) => Err(DfrError::AlreadyInUse { path }),
+            Err(TryLockError::Error(err)) => Err(err.into()),
+        }
+    }
+
     fn read_byte(&mut self, reg: Register) -> Result<u8, IoError> {
-        self.dev.smbus_read_byte_data(reg as u8)
+        self.read_byte_at(reg as u8)
+    }
+
+    fn read_byte_at(&mut self, reg: u8) -> Result<u8, IoError> {
+        let value = lock_bus(&self.dev).read_byte_data(reg)?;
+        self.observe(reg, TransactionKind::Read, &[value]);
+
+        Ok(value)
     }
 
     fn read_bytes(&mut self, reg: Register, count: u8) -> Result<Vec<u8>, IoError> {
-        let mut buf = Vec::with_capacity(count as usize);
+        let mut buf = vec![0; count as usize];
 
-        self.dev.smbus_read_block_data(reg as u8, &mut buf)?;
+        let len = self.read_bytes_into(reg, &mut buf)?;
+        expect_len(buf.len(), len)?;
 
         Ok(buf)
     }
 
+    /// Read a block into [`buf`] without allocating, returning how many bytes were read.
+    fn read_bytes_into(&mut self, reg: Register, buf: &mut [u8]) -> Result<usize, IoError> {
+        debug_assert!(matches!(reg.transfer_kind(), TransferKind::I2cBlock));
+        let len = lock_bus(&self.dev).read_i2c_block_data(reg as u8, buf)?;
+        self.observe(reg as u8, TransactionKind::Read, &buf[..len]);
+
+        Ok(len)
+    }
+
     fn write_bytes(&mut self, reg: Register, bytes: &[u8]) -> Result<(), IoError> {
-        self.dev.smbus_write_block_data(reg as u8, bytes)?;
+        match reg.transfer_kind() {
+            TransferKind::Byte => {
+                assert_eq!(bytes.len(), 1);
+                self.write_byte_at(reg as u8, bytes[0])
+            }
+            TransferKind::I2cBlock => self.write_bytes_at(reg as u8, bytes),
+        }
+    }
+
+    fn write_byte_at(&mut self, reg: u8, value: u8) -> Result<(), IoError> {
+        self.reset_done = false;
+        lock_bus(&self.dev).write_byte_data(reg, value)?;
+        self.observe(reg, TransactionKind::Write, &[value]);
+
+        Ok(())
+    }
+
+    fn write_bytes_at(&mut self, reg: u8, bytes: &[u8]) -> Result<(), IoError> {
+        self.reset_done = false;
+        lock_bus(&self.dev).write_i2c_block_data(reg, bytes)?;
+        self.observe(reg, TransactionKind::Write, bytes);
+
+        Ok(())
+    }
+
+    /// Call [`hook`] on every successful transaction to a register of the board from now on, e.g. to log or record the protocol.
+    /// The hook runs synchronously, in the middle of the method issuing the transaction, so it should return quickly.
+    /// It doesn't see the watchdog, which writes directly to the bus from its own thread, nor failed transactions.
+    pub fn set_transaction_hook(&mut self, hook: impl FnMut(Transaction<'_>) + Send + 'static) {
+        self.hook = Some(Box::new(hook));
+    }
+
+    /// Stop calling the hook set with [`set_transaction_hook`], if any.
+    pub fn clear_transaction_hook(&mut self) {
+        self.hook = None;
+    }
+
+    fn observe(&mut self, register: u8, kind: TransactionKind, bytes: &[u8]) {
+        if let Some(hook) = &mut self.hook {
+            hook(Transaction { register, kind, bytes });
+        }
+    }
+
+    /// Write a single byte register, then read it back and fail with [`DfrError::VerifyMismatch`] if the board didn't latch the value.
+    pub fn write_register_verified(&mut self, reg: u8, value: u8) -> Result<(), DfrError> {
+        if reg == Register::PwmCtrl as u8 {
+            self.pwm_enabled = None;
+        } else if reg == Register::AdcCtrl as u8 {
+            self.adc_enabled = None;
+        }
+        self.write_byte_at(reg, value)?;
+        let read = self.read_byte_at(reg)?;
+
+        if read != value {
+            return Err(DfrError::VerifyMismatch { wrote: value, read });
+        }
+
+        Ok(())
+    }
 
+    /// Enable or disable SMBus packet error checking on the handle, so that corrupted transactions fail instead of going unnoticed.
+    /// Only helps if the firmware supports PEC, which the reference library never enables: if it doesn't, every transaction fails once enabled.
+    pub fn set_pec(&mut self, enable: bool) -> Result<(), IoError> {
+        lock_bus(&self.dev).set_pec(enable)
+    }
+
+    /// Wait until every write issued so far has reached the board.
+    /// Every SMBus write currently completes before its method returns, so this is a no-op kept as an explicit barrier.
+    pub fn flush(&mut self) -> Result<(), IoError> {
         Ok(())
     }
 
     /// Instantiate the IO Expansion Board
-    fn begin(&mut self) -> Result<(), Box<dyn Error>> {
-        let pid = self.read_byte(Register::PID)?;
-        let vid = self.read_byte(Register::VID)?;
+    fn begin(&mut self) -> Result<(), DfrError> {
+        let info = self.read_info().map_err(|source| DfrError::NoResponse { source })?;
+
+        if !self.options.identities.contains(&info) {
+            if !self.options.identities.iter().any(|accepted| accepted.pid == info.pid) {
+                return Err(DfrError::WrongDevice { pid: info.pid, vid: info.vid });
+            }
+
+            if !self.options.allow_version_mismatch {
+                return Err(DfrError::SoftVersion);
+            }
+        }
+        self.identity = info;
+
+        self.reset()?;
+        if let Some(freq) = self.options.frequency {
+            self.set_pwm_freq(freq)?;
+        }
+        if self.options.pwm_enabled_on_open {
+            self.enable_pwm(true)?;
+        }
+
+        Ok(())
+    }
 
-        if pid != Register::DefPID as u8 {
-            return Err(Box::new(BoardError::DeviceNotDetected));
+    /// Check the identity of the board again and reset it, through the existing handle, as when it was opened.
+    /// Use after the board was power cycled while the bus stayed up. Forgets the cached PWM frequency.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn reinit(&mut self) -> Result<(), DfrError> {
+        self.frequency = None;
+        self.freq_configured = false;
+
+        self.begin()
+    }
+
+    /// Change the I2C address of the board, between `0x01` and `0x7F`, verifying the board took the new value.
+    /// The board keeps answering on its current address until it's power cycled.
+    pub fn set_slave_address(&mut self, addr: u8) -> Result<(), DfrError> {
+        if !(0x01..=0x7F).contains(&addr) {
+            return Err(DfrError::InvalidParameter("address must be between 0x01 and 0x7F"));
         }
 
-        if vid != Register::DefVID as u8 {
-            return Err(Box::new(BoardError::SoftVersion));
+        self.write_register_verified(Register::SlaveAddr as u8, addr)
+    }
+
+    /// Change the I2C address of the board like [`set_slave_address`], then point the handle at the new address and read the product ID
+    /// there to confirm the board answers. Fails with [`DfrError::AddressChangeFailed`] if it doesn't, leaving the handle on the new address.
+    /// Boards that only move once power cycled, as the reference library documents, fail the check until then.
+    pub fn change_address_verified(&mut self, new_addr: u8) -> Result<(), DfrError> {
+        self.set_slave_address(new_addr)?;
+
+        lock_bus(&self.dev).set_slave_address(new_addr)?;
+        self.options.addr = new_addr;
+
+        match self.read_byte(Register::PID) {
+            Ok(pid) if pid == self.identity.pid => Ok(()),
+            _ => Err(DfrError::AddressChangeFailed { requested: new_addr }),
         }
+    }
 
+    /// Bring the board back to its factory state: the default address (0x10), with both subsystems disabled and all duty cycles zeroed.
+    /// The address is the only setting the firmware persists, and like with [`set_slave_address`] it only takes effect once the board is power cycled.
+    /// There's no factory reset command: [`DEFAULT_PID`] and [`DEFAULT_VID`] are the identity the board reports, not registers.
+    pub fn factory_reset(&mut self) -> Result<(), DfrError> {
+        self.set_slave_address(DEFAULT_ADDRESS)?;
         self.reset()?;
 
         Ok(())
     }
 
-    pub fn reset(&mut self) -> Result<(), IoError> {
-        self.enable_pwm(false)?;
-        for ch in Channel::all() {
-            self.set_pwm_duty(ch, 0.0)?;
+    /// Whether the board looks untouched since it left the factory: the address register (`0x00`) holds [`DEFAULT_ADDRESS`],
+    /// both control registers (`0x03`, `0x0E`) are disabled, and the four duty registers (`0x06` to `0x0D`) are zeroed.
+    /// Only the address persists across power cycles, the rest is also the state [`reset`] leaves the board in, as when it's opened.
+    /// The frequency register isn't inspected: the firmware's power-on frequency isn't documented.
+    pub fn is_factory_default(&mut self) -> Result<bool, IoError> {
+        Ok(self.read_byte(Register::SlaveAddr)? == DEFAULT_ADDRESS
+            && !self.is_pwm_enabled()?
+            && !self.is_adc_enabled()?
+            && self.read_bytes(Register::PwmDuty0, 2 * CHANNEL_COUNT as u8)?.iter().all(|&byte| byte == 0))
+    }
+
+    /// Read the identity of the board. Updates [`version_warning`]: cleared if it's an accepted identity, set otherwise.
+    pub fn read_info(&mut self) -> Result<BoardInfo, IoError> {
+        let info = BoardInfo {
+            pid: self.read_byte(Register::PID)?,
+            vid: self.read_byte(Register::VID)?,
+        };
+
+        self.version_warning = if self.options.identities.contains(&info) {
+            None
+        } else {
+            Some(info)
+        };
+
+        Ok(info)
+    }
+
+    /// The identity the board reported when it was opened, one of [`DfrIoHatBuilder::accept_identities`]
+    /// unless opened with [`DfrIoHatBuilder::allow_version_mismatch`].
+    pub fn identity(&self) -> BoardInfo {
+        self.identity
+    }
+
+    /// The identity of the board, if it wasn't an accepted one the last time it was read.
+    /// A board with a mismatching version only opens with [`DfrIoHatBuilder::allow_version_mismatch`].
+    pub fn version_warning(&self) -> Option<BoardInfo> {
+        self.version_warning
+    }
+
+    /// Time [`iterations`] single byte reads of the product ID register, and return the average round-trip of one transaction.
+    pub fn measure_latency(&mut self, iterations: u32) -> Result<Duration, IoError> {
+        assert!(iterations >= 1);
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            self.read_byte(Register::PID)?;
         }
-        self.enable_adc(false)?;
+
+        Ok(start.elapsed() / iterations)
+    }
+
+    /// Disable both subsystems and zero the duty cycle of all channels, in three transactions:
+    /// the duty registers are contiguous, but the two control registers aren't. Always writes the control registers.
+    pub fn reset(&mut self) -> Result<(), IoError> {
+        self.force_enable_pwm(false)?;
+        self.set_pwm_duties([0f32; CHANNEL_COUNT])?;
+        self.force_enable_adc(false)?;
+        self.reset_done = true;
+
+        Ok(())
+    }
+
+    /// Disable the PWM subsystem and zero the duty cycle of all channels, leaving the ADC untouched.
+    pub fn all_off(&mut self) -> Result<(), IoError> {
+        self.pwm_enabled = None;
+        self.write_byte_at(Register::PwmCtrl as u8, 0x00)?;
+        self.write_bytes_at(Register::PwmDuty0 as u8, &[0x00; 2 * CHANNEL_COUNT])?;
+        self.pwm_enabled = Some(false);
+        // That's all of a reset if the ADC is already known to be disabled.
+        self.reset_done = self.adc_enabled == Some(false);
+        #[cfg(feature = "tracing")]
+        tracing::debug!("all outputs off");
+
+        Ok(())
+    }
+
+    /// Counterpart of [`reset`] that only tears down the outputs, leaving the ADC enabled, e.g. to recover mid-acquisition.
+    /// Unlike [`all_off`], the duty cycles are zeroed as with [`set_pwm_duties`], so that inverted channels end up logically off.
+    pub fn reset_outputs_only(&mut self) -> Result<(), IoError> {
+        self.force_enable_pwm(false)?;
+        self.set_pwm_duties([0f32; CHANNEL_COUNT])?;
+
+        Ok(())
+    }
+
+    /// Start a software watchdog that calls [`all_off`] if [`feed_watchdog`] isn't called at least once every [`timeout`].
+    /// Replaces the previous watchdog, if any.
+    ///
+    /// The watchdog runs on its own thread, which shares the bus handle with this instance: every transaction
+    /// of the driver, and the watchdog's own shutdown, takes the same lock for the duration of the transaction.
+    /// A trip can thus only land between two transactions, never in the middle of one; multi-transaction
+    /// operations (e.g. a fade) aren't atomic with respect to it, and can write outputs again after a trip
+    /// if the caller wasn't the one stalling.
+    ///
+    /// The watchdog only trips once, after which [`watchdog_tripped`] returns `true` and the outputs stay off until
+    /// they're written again. It's stopped when this instance is dropped or [`stop_watchdog`] is called.
+    pub fn start_watchdog(&mut self, timeout: Duration) -> Result<(), IoError> {
+        self.watchdog = None;
+        self.watchdog = Some(Watchdog::start(self.dev.clone(), timeout)?);
 
         Ok(())
     }
 
+    /// Reset the watchdog timer. Does nothing if no watchdog is running.
+    pub fn feed_watchdog(&mut self) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.feed();
+        }
+    }
+
+    /// Stop the watchdog, if any, without touching the outputs.
+    pub fn stop_watchdog(&mut self) {
+        self.watchdog = None;
+    }
+
+    /// Whether the current watchdog has timed out and turned the outputs off.
+    pub fn watchdog_tripped(&self) -> bool {
+        self.watchdog.as_ref().is_some_and(|watchdog| watchdog.tripped())
+    }
+
     /// Activate the PWM subsystem
+    ///
+    /// Skips the write if the last one already set the same state, and the watchdog hasn't tripped since; see [`force_enable_pwm`].
     pub fn enable_pwm(&mut self, enable: bool) -> Result<(), IoError> {
+        if self.pwm_enabled == Some(enable) && !self.watchdog_tripped() && !self.options.minimal {
+            return Ok(());
+        }
+
+        self.force_enable_pwm(enable)
+    }
+
+    /// Same as [`enable_pwm`], but always writes the control register, e.g. to recover from a board that was power cycled.
+    pub fn force_enable_pwm(&mut self, enable: bool) -> Result<(), IoError> {
+        self.pwm_enabled = None;
         if enable {
             self.write_bytes(Register::PwmCtrl, &[0x01])?;
         } else {
             self.write_bytes(Register::PwmCtrl, &[0x00])?;
         }
+        self.pwm_enabled = Some(enable);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(enabled = enable, "PWM subsystem");
 
         Ok(())
     }
 
     /// Activate the ADC subsystem
+    ///
+    /// The control register is a bare enable flag: the reference library only ever writes `0x01` or `0x00` to it,
+    /// and exposes no conversion-ready or status bit. Once enabled, the firmware converts continuously on its own.
+    ///
+    /// Skips the write if the last one already set the same state; see [`force_enable_adc`].
     pub fn enable_adc(&mut self, enable: bool) -> Result<(), IoError> {
+        if self.adc_enabled == Some(enable) && !self.options.minimal {
+            return Ok(());
+        }
+
+        self.force_enable_adc(enable)
+    }
+
+    /// Same as [`enable_adc`], but always writes the control register, e.g. to recover from a board that was power cycled.
+    pub fn force_enable_adc(&mut self, enable: bool) -> Result<(), IoError> {
+        self.adc_enabled = None;
         if enable {
             self.write_bytes(Register::AdcCtrl, &[0x01])?;
         } else {
             self.write_bytes(Register::AdcCtrl, &[0x00])?;
         }
+        self.adc_enabled = Some(enable);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(enabled = enable, "ADC subsystem");
 
         Ok(())
     }
 
+    /// Read the raw contents of the PWM control register.
+    pub fn pwm_ctrl_raw(&mut self) -> Result<u8, IoError> {
+        self.read_byte(Register::PwmCtrl)
+    }
+
+    /// Read the raw contents of the ADC control register.
+    pub fn adc_ctrl_raw(&mut self) -> Result<u8, IoError> {
+        self.read_byte(Register::AdcCtrl)
+    }
+
+    /// Whether the PWM subsystem is currently active
+    pub fn is_pwm_enabled(&mut self) -> Result<bool, IoError> {
+        Ok(self.pwm_ctrl_raw()? & 0x01 != 0)
+    }
+
+    /// Read the enable bits of the PWM control register. The reference library only ever sets bit 0, enabling all channels at once.
+    pub fn pwm_enabled_mask(&mut self) -> Result<u8, IoError> {
+        self.pwm_ctrl_raw()
+    }
+
+    /// Check the PWM subsystem is in the state [`enable_pwm`] last set, e.g. to catch a glitch that silently cleared it,
+    /// and return whether it was. On a mismatch, the hardware state replaces the remembered one, and with the `tracing`
+    /// feature a warning is emitted. Always agrees if the state is unknown, e.g. before the first write.
+    pub fn reconcile_pwm_enabled(&mut self) -> Result<bool, IoError> {
+        let enabled = self.pwm_enabled_mask()? & 0x01 != 0;
+
+        let agrees = self.pwm_enabled.is_none_or(|cached| cached == enabled);
+        if !agrees {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(expected = !enabled, actual = enabled, "PWM subsystem state disagrees with the hardware");
+        }
+        self.pwm_enabled = Some(enabled);
+
+        Ok(agrees)
+    }
+
+    /// Whether the ADC subsystem is currently active
+    pub fn is_adc_enabled(&mut self) -> Result<bool, IoError> {
+        Ok(self.adc_ctrl_raw()? & 0x01 != 0)
+    }
+
     /// Set the PWM duty cycle.
     /// Valid [`duty`] values are between ``0.000` and `1.000`.
     pub fn set_pwm_duty(&mut self, channel: Channel, duty: f32) -> Result<(), IoError> {
-        assert!(duty >= 0f32);
-        assert!(duty <= 1f32);
-        let duty = (duty * 1e2) as u16;
-        let bytes = [duty as u8, ((duty * 10) % 10) as u8]; // This is from the reference library and I'm not gonna question it
+        if let Some(batch) = &mut self.batch {
+            assert!((0f32..=1f32).contains(&duty));
+            batch[channel.index()] = Some(duty);
+
+            return Ok(());
+        }
+
+        let bytes = duty_to_bytes(self.register_duty(channel.index(), duty));
 
         match channel {
             Channel::Ch0 => self.write_bytes(Register::PwmDuty0, &bytes)?,
@@ -142,69 +795,1725 @@ impl DfrIoHat {
             Channel::Ch2 => self.write_bytes(Register::PwmDuty2, &bytes)?,
             Channel::Ch3 => self.write_bytes(Register::PwmDuty3, &bytes)?,
         }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?channel, duty, "PWM duty");
 
         Ok(())
     }
 
-    /// Set the PWM frequency for the entire board.
-    /// Valid [`freq`] values are between `1` and `1000`.
-    pub fn set_pwm_freq(&mut self, freq: u16) -> Result<(), IoError> {
-        assert!(freq >= 1);
-        assert!(freq <= 1000);
-        let bytes = freq.to_be_bytes();
+    /// Same as [`set_pwm_duty`], with the channel given by its index, and failing with [`DfrError::InvalidDuty`] on an invalid [`duty`].
+    pub fn set_pwm_duty_by_index(&mut self, idx: u8, duty: f32) -> Result<(), DfrError> {
+        let channel = Channel::try_from(idx)?;
+        if !(0f32..=1f32).contains(&duty) {
+            return Err(DfrError::InvalidDuty(duty));
+        }
 
-        self.write_bytes(Register::PwmFreq, &bytes)?;
+        Ok(self.set_pwm_duty(channel, duty)?)
+    }
+
+    /// Start buffering the writes of [`set_pwm_duty`], and of the helpers built on it, until [`commit_batch`] writes them
+    /// all at once. Only [`set_pwm_duty`] is buffered: every other write to the duty registers, e.g. [`set_pwm_duties`],
+    /// [`set_pwm_duty_permille`] (and so the servo methods), [`reset`] or [`park`], still reaches the board immediately.
+    ///
+    /// The firmware has no latch register to apply duty cycles on the same PWM period: committing writes all four duty registers
+    /// in a single block write, so they change within one transaction, but the board may still apply them a period apart.
+    pub fn begin_batch(&mut self) -> Result<(), IoError> {
+        self.batch = Some([None; CHANNEL_COUNT]);
 
         Ok(())
     }
 
-    /// Get the value of the specified ADC pin, it will return a value between `0` and `1023`.
-    pub fn get_adc_value(&mut self, channel: Channel) -> Result<u16, IoError> {
+    /// Write the duty cycles buffered since [`begin_batch`] in a single transaction, and stop buffering. Does nothing if no batch
+    /// was started, or if nothing was buffered. Channels the batch didn't touch keep what their registers hold at the time of the commit,
+    /// read back first unless the batch touched every channel, so writes that bypassed the batch aren't reverted.
+    pub fn commit_batch(&mut self) -> Result<(), IoError> {
+        let Some(batch) = self.batch.take() else {
+            return Ok(());
+        };
+        if batch.iter().all(Option::is_none) {
+            return Ok(());
+        }
+
+        let mut bytes = [0u8; 2 * CHANNEL_COUNT];
+        if batch.iter().any(Option::is_none) {
+            let current = self.read_bytes(Register::PwmDuty0, 2 * CHANNEL_COUNT as u8)?;
+            bytes.copy_from_slice(&current);
+        }
+        for (i, (chunk, duty)) in bytes.chunks_exact_mut(2).zip(batch).enumerate() {
+            if let Some(duty) = duty {
+                chunk.copy_from_slice(&duty_to_bytes(self.register_duty(i, duty)));
+            }
+        }
+
+        self.write_bytes(Register::PwmDuty0, &bytes)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?batch, "PWM duties batch");
+
+        Ok(())
+    }
+
+    /// Set the duty cycles, indexed by channel, that [`set_pwm_duty_safe`] falls back to when a write fails.
+    pub fn set_failsafe(&mut self, duties: [f32; CHANNEL_COUNT]) {
+        assert!(duties.iter().all(|duty| (0f32..=1f32).contains(duty)));
+
+        self.failsafe = Some(duties);
+    }
+
+    /// Same as [`set_pwm_duty`], but if the write fails and a failsafe was set with [`set_failsafe`], try to apply the failsafe
+    /// duty cycles to all channels before returning the error. Applying the failsafe is best-effort: it's one more write
+    /// on a bus that just failed, and its own error, if any, is discarded.
+    pub fn set_pwm_duty_safe(&mut self, channel: Channel, duty: f32) -> Result<(), IoError> {
+        let result = self.set_pwm_duty(channel, duty);
+
+        if result.is_err() {
+            if let Some(failsafe) = self.failsafe {
+                let _ = self.set_pwm_duties(failsafe);
+            }
+        }
+
+        result
+    }
+
+    /// Smallest duty cycle increment the board can represent at its current frequency.
+    ///
+    /// The duty registers hold tenths of a percent whatever the frequency, so this is always `0.001`: the firmware
+    /// doesn't expose its timer resolution, which may be coarser at high frequencies. Note that [`set_pwm_duty`] only
+    /// writes whole percents, use [`set_pwm_duty_permille`] for the full resolution.
+    pub fn duty_resolution(&mut self) -> Result<f32, IoError> {
+        Ok(1e-3)
+    }
+
+    /// Set the PWM duty cycle in tenths of a percent, the resolution of the duty registers, bypassing float rounding.
+    /// Valid [`permille`] values are between `0` and `1000`. Inversion and duty limits apply as with [`set_pwm_duty`].
+    pub fn set_pwm_duty_permille(&mut self, channel: Channel, permille: u16) -> Result<(), DfrError> {
+        if permille > 1000 {
+            return Err(DfrError::InvalidDuty(permille as f32 / 1e3));
+        }
+
+        let i = channel.index();
+        let (min, max) = self.duty_limits[i];
+        let mut permille = permille.clamp((min * 1e3).ceil() as u16, (max * 1e3).floor() as u16);
+        if self.inverted[i] {
+            permille = 1000 - permille;
+        }
+        let bytes = permille_to_bytes(permille);
+
+        match channel {
+            Channel::Ch0 => self.write_bytes(Register::PwmDuty0, &bytes)?,
+            Channel::Ch1 => self.write_bytes(Register::PwmDuty1, &bytes)?,
+            Channel::Ch2 => self.write_bytes(Register::PwmDuty2, &bytes)?,
+            Channel::Ch3 => self.write_bytes(Register::PwmDuty3, &bytes)?,
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?channel, register_permille = permille, "PWM duty");
+
+        Ok(())
+    }
+
+    /// Set the PWM duty cycle in whole percents, writing the same bytes as the reference library: the percent, then `0`.
+    /// Valid [`percent`] values are between `0` and `100`. Inversion and duty limits apply as with [`set_pwm_duty_permille`],
+    /// which can produce a non-zero tenths byte.
+    pub fn set_pwm_duty_percent_int(&mut self, channel: Channel, percent: u8) -> Result<(), DfrError> {
+        if percent > 100 {
+            return Err(DfrError::InvalidDuty(percent as f32 / 1e2));
+        }
+
+        self.set_pwm_duty_permille(channel, percent as u16 * 10)
+    }
+
+    /// Set the PWM duty cycle of all channels in a single transaction, indexed by channel.
+    /// Valid [`duties`] values are between `0.000` and `1.000`.
+    pub fn set_pwm_duties(&mut self, duties: [f32; CHANNEL_COUNT]) -> Result<(), IoError> {
+        let mut bytes = [0u8; 2 * CHANNEL_COUNT];
+        for (i, (chunk, duty)) in bytes.chunks_exact_mut(2).zip(duties).enumerate() {
+            chunk.copy_from_slice(&duty_to_bytes(self.register_duty(i, duty)));
+        }
+
+        self.write_bytes(Register::PwmDuty0, &bytes)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?duties, "PWM duties");
+
+        Ok(())
+    }
+
+    /// Read back the PWM duty cycle of the specified channel, as a value between `0.000` and `1.000`.
+    pub fn get_pwm_duty(&mut self, channel: Channel) -> Result<f32, IoError> {
+        let i = channel.index();
         let bytes = match channel {
-            Channel::Ch0 => self.read_bytes(Register::AdcCh0, 2)?,
-            Channel::Ch1 => self.read_bytes(Register::AdcCh1, 2)?,
-            Channel::Ch2 => self.read_bytes(Register::AdcCh2, 2)?,
-            Channel::Ch3 => self.read_bytes(Register::AdcCh3, 2)?,
+            Channel::Ch0 => self.read_bytes(Register::PwmDuty0, 2)?,
+            Channel::Ch1 => self.read_bytes(Register::PwmDuty1, 2)?,
+            Channel::Ch2 => self.read_bytes(Register::PwmDuty2, 2)?,
+            Channel::Ch3 => self.read_bytes(Register::PwmDuty3, 2)?,
         };
 
-        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+        Ok(self.apply_invert(i, duty_from_bytes([bytes[0], bytes[1]])))
     }
-}
 
-impl Drop for DfrIoHat {
-    fn drop(&mut self) {
-        let _ = self.reset();
+    /// Read back the duty cycle the board is actually running the specified channel at, after the quantization of the duty registers:
+    /// [`set_pwm_duty`] truncates to whole percents, and the registers hold tenths of a percent at best. Writes nothing.
+    /// Same as [`get_pwm_duty`], useful to log the difference from the requested duty cycle.
+    pub fn effective_duty(&mut self, channel: Channel) -> Result<f32, IoError> {
+        self.get_pwm_duty(channel)
     }
-}
 
-impl Debug for BoardError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "BoardStatus::{}", match self {
-            BoardError::DeviceNotDetected => "DeviceNotDetected",
-            BoardError::SoftVersion => "SoftVersion",
-        })
+    /// Whether [`set_pwm_duty`] can run a channel at exactly [`duty`]: only whole percents between `0.00` and `1.00`
+    /// (the `f32` nearest to each, e.g. `0.53`) survive its quantization. Ignores inversion, see [`set_pwm_invert`]. Touches no register.
+    pub fn is_duty_representable(&self, duty: f32) -> bool {
+        (0f32..=1f32).contains(&duty) && self.nearest_representable_duty(duty) == duty
     }
-}
 
-impl Display for BoardError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", match self {
-            BoardError::DeviceNotDetected => "Device not detected.",
-            BoardError::SoftVersion => "Firmware/software version mismatch.",
+    /// The duty cycle the board actually runs at when [`set_pwm_duty`] is given [`duty`], clamped between `0.000` and `1.000` (NaN counts as `0.000`).
+    /// [`set_pwm_duty`] rounds to tenths of a percent, then truncates to whole percents: this is the whole percent at or below [`duty`],
+    /// unless [`duty`] is within half a tenth of a percent of the next one, e.g. `0.539` runs at `0.53` but `0.5396` at `0.54`.
+    /// Ignores inversion, see [`set_pwm_invert`]. Touches no register.
+    pub fn nearest_representable_duty(&self, duty: f32) -> f32 {
+        let duty = if duty.is_nan() { 0f32 } else { duty.clamp(0f32, 1f32) };
+
+        duty_from_bytes(duty_to_bytes(duty))
+    }
+
+    /// Check that the duty registers hold what the encoding of [`set_pwm_duty`] and [`set_pwm_duty_permille`] assumes
+    /// (percent, then tenths of a percent), by writing `0.5` and `0.505` to the duty register of [`Channel::Ch0`] and reading them back.
+    /// Returns `false` if either doesn't round-trip, e.g. on a firmware revision that encodes the registers differently.
+    ///
+    /// This only checks what the registers store, not the waveform the firmware makes of it: measure the output for that.
+    /// The channel briefly runs at those duty cycles if PWM is enabled. Its register is restored afterwards, even on a mismatch.
+    pub fn verify_duty_encoding(&mut self) -> Result<bool, IoError> {
+        let prior = self.read_bytes(Register::PwmDuty0, 2)?;
+
+        let mut matches = true;
+        for (bytes, expected) in [(duty_to_bytes(0.5), 0.5), (permille_to_bytes(505), 0.505)] {
+            self.write_bytes(Register::PwmDuty0, &bytes)?;
+            let read = self.read_bytes(Register::PwmDuty0, 2)?;
+            matches &= (duty_from_bytes([read[0], read[1]]) - expected).abs() < 1e-4;
+        }
+        self.write_bytes(Register::PwmDuty0, &prior)?;
+        #[cfg(feature = "tracing")]
+        if !matches {
+            tracing::warn!("duty registers don't round-trip, the firmware may encode them differently");
+        }
+
+        Ok(matches)
+    }
+
+    /// Read back the PWM duty cycle of all channels in a single transaction, indexed by channel.
+    pub fn get_pwm_duties(&mut self) -> Result<[f32; CHANNEL_COUNT], IoError> {
+        let bytes = self.read_bytes(Register::PwmDuty0, 2 * CHANNEL_COUNT as u8)?;
+
+        let mut duties = [0f32; CHANNEL_COUNT];
+        for (i, (duty, chunk)) in duties.iter_mut().zip(bytes.chunks_exact(2)).enumerate() {
+            *duty = self.apply_invert(i, duty_from_bytes([chunk[0], chunk[1]]));
+        }
+
+        Ok(duties)
+    }
+
+    /// Check whether a channel is pinned at a rail, e.g. because a control loop saturated:
+    /// `Some(true)` at a duty cycle of `1.0`, `Some(false)` at `0.0`, and `None` anywhere in between.
+    pub fn pwm_at_rail(&mut self, channel: Channel) -> Result<Option<bool>, IoError> {
+        let duty = self.get_pwm_duty(channel)?;
+
+        Ok(if duty >= 1f32 {
+            Some(true)
+        } else if duty <= 0f32 {
+            Some(false)
+        } else {
+            None
         })
     }
-}
 
-impl Error for BoardError {}
+    /// Set the duty cycles, indexed by channel, that [`park`] applies. Defaults to all zeroes.
+    pub fn set_park_duties(&mut self, duties: [f32; CHANNEL_COUNT]) {
+        assert!(duties.iter().all(|duty| (0f32..=1f32).contains(duty)));
 
-impl Channel {
-    /// Return an iterator over all the channels
-    pub fn all() -> [Channel; 4] {
-        [
-            Channel::Ch0,
-            Channel::Ch1,
-            Channel::Ch2,
-            Channel::Ch3,
-        ]
+        self.park_duties = duties;
+    }
+
+    /// Remember the duty cycles of all channels, and apply the ones set with [`set_park_duties`] until [`unpark`] is called,
+    /// e.g. while a machine pauses between jobs. Parking again before unparking keeps the duty cycles remembered the first time.
+    pub fn park(&mut self) -> Result<(), IoError> {
+        if self.parked.is_none() {
+            self.parked = Some(self.get_pwm_duties()?);
+        }
+
+        self.set_pwm_duties(self.park_duties)
+    }
+
+    /// Restore the duty cycles remembered by [`park`]. Does nothing if the outputs aren't parked.
+    pub fn unpark(&mut self) -> Result<(), IoError> {
+        if let Some(duties) = self.parked {
+            self.set_pwm_duties(duties)?;
+            self.parked = None;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the outputs are parked, see [`park`].
+    pub fn is_parked(&self) -> bool {
+        self.parked.is_some()
+    }
+
+    /// Drive a channel as a plain on/off output, e.g. for a relay: "on" is a duty cycle of `1.0` at the current frequency, "off" is `0.0`.
+    /// Enables the PWM subsystem if it wasn't already.
+    pub fn digital_write(&mut self, channel: Channel, on: bool) -> Result<(), IoError> {
+        let result = self.set_pwm_duty(channel, if on { 1f32 } else { 0f32 });
+        self.tolerate(result)?;
+        let result = self.enable_pwm(true);
+        self.tolerate(result)?;
+
+        Ok(())
+    }
+
+    /// Set a channel to [`duty`] for [`duration`], then back to the duty cycle it had before, e.g. to fire a relay or solenoid.
+    /// Blocks for the duration of the pulse.
+    pub fn pulse(&mut self, channel: Channel, duty: f32, duration: Duration) -> Result<(), DfrError> {
+        if !(0f32..=1f32).contains(&duty) {
+            return Err(DfrError::InvalidDuty(duty));
+        }
+
+        let prior = self.get_pwm_duty(channel)?;
+        self.set_pwm_duty(channel, duty)?;
+        thread::sleep(duration);
+        self.set_pwm_duty(channel, prior)?;
+
+        Ok(())
+    }
+
+    /// Alternate a channel between [`high`] and [`low`] for [`cycles`] periods of [`period`], spending half of each at either level,
+    /// e.g. for a strobe. Starts high and finishes low. Blocks for the duration of the strobe.
+    pub fn strobe(&mut self, channel: Channel, low: f32, high: f32, period: Duration, cycles: u32) -> Result<(), DfrError> {
+        for duty in [low, high] {
+            if !(0f32..=1f32).contains(&duty) {
+                return Err(DfrError::InvalidDuty(duty));
+            }
+        }
+
+        let half = period / 2;
+        for _ in 0..cycles {
+            self.set_pwm_duty(channel, high)?;
+            thread::sleep(half);
+            self.set_pwm_duty(channel, low)?;
+            thread::sleep(half);
+        }
+
+        Ok(())
+    }
+
+    /// Flash an error code on a status LED: [`code`] flashes of 200ms on and 200ms off, with a further 800ms of darkness
+    /// after each of the [`repeats`] repetitions, so codes are separated by a full second. Built on [`digital_write`](Self::digital_write),
+    /// and blocks until the last repetition is done.
+    pub fn blink_code(&mut self, channel: Channel, code: u8, repeats: u32) -> Result<(), IoError> {
+        const FLASH: Duration = Duration::from_millis(200);
+        const PAUSE: Duration = Duration::from_millis(800);
+
+        for _ in 0..repeats {
+            for _ in 0..code {
+                self.digital_write(channel, true)?;
+                thread::sleep(FLASH);
+                self.digital_write(channel, false)?;
+                thread::sleep(FLASH);
+            }
+            thread::sleep(PAUSE);
+        }
+
+        Ok(())
+    }
+
+    /// Sound a tone of [`freq`] hertz on a buzzer for [`duration`], with a square wave of duty cycle `0.5`, then silence the channel.
+    /// Enables the PWM subsystem if it wasn't already. The frequency is shared by the whole board: it's changed for the duration
+    /// of the tone, and set back to what it was after, as with [`with_freq`](Self::with_freq). Blocks for the duration of the tone.
+    pub fn tone(&mut self, channel: Channel, freq: u16, duration: Duration) -> Result<(), DfrError> {
+        self.with_freq(freq, |hat| {
+            hat.set_pwm_duty(channel, 0.5)?;
+            hat.enable_pwm(true)?;
+            thread::sleep(duration);
+            hat.set_pwm_duty(channel, 0f32)
+        })??;
+
+        Ok(())
+    }
+
+    /// Play a rising two-note chime on a buzzer, to confirm something worked: 523Hz (C5) then 784Hz (G5), 100ms each.
+    pub fn beep_ok(&mut self, channel: Channel) -> Result<(), DfrError> {
+        self.tone(channel, 523, Duration::from_millis(100))?;
+        self.tone(channel, 784, Duration::from_millis(100))?;
+
+        Ok(())
+    }
+
+    /// Play three low beeps on a buzzer, to signal a failure: 220Hz (A3) for 150ms, 100ms apart.
+    pub fn beep_error(&mut self, channel: Channel) -> Result<(), DfrError> {
+        for i in 0..3 {
+            if i > 0 {
+                thread::sleep(Duration::from_millis(100));
+            }
+            self.tone(channel, 220, Duration::from_millis(150))?;
+        }
+
+        Ok(())
+    }
+
+    /// List the channels with a non-zero duty cycle, from a single read of the duty registers.
+    pub fn active_channels(&mut self) -> Result<Vec<Channel>, IoError> {
+        let duties = self.get_pwm_duties()?;
+
+        Ok(Channel::all().into_iter()
+            .zip(duties)
+            .filter(|(_, duty)| *duty > 0f32)
+            .map(|(channel, _)| channel)
+            .collect())
+    }
+
+    /// Invert the duty cycle of a channel, e.g. for common-anode loads where `0.0` is fully on.
+    /// While inverted, every duty cycle given to or read back from the channel is the logical value: `1.0 - duty` is what's written.
+    pub fn set_pwm_invert(&mut self, channel: Channel, invert: bool) {
+        self.inverted[channel.index()] = invert;
+    }
+
+    /// Restrict the duty cycle of a channel to [`min`]..=[`max`], e.g. to keep a fan spinning or cap a heater.
+    /// Every duty cycle written to the channel from then on is clamped to the limits, including by [`reset`],
+    /// so the readback reports the clamped value actually written. The limits must be a sub-range of `0.000` to `1.000`.
+    /// As [`set_pwm_duty`] writes whole percents, the limits are rounded inwards to whole percents for it, and must hold at
+    /// least one: `0.255` keeps its duty cycles at `0.26` or more, while [`set_pwm_duty_permille`] can go down to `0.255`.
+    pub fn set_duty_limits(&mut self, channel: Channel, min: f32, max: f32) {
+        assert!(min >= 0f32);
+        assert!(max <= 1f32);
+        assert!(min <= max);
+        let (min_percent, max_percent) = percent_limits(min, max);
+        assert!(min_percent <= max_percent);
+
+        self.duty_limits[channel.index()] = (min, max);
+    }
+
+    /// Validate a logical duty cycle for the channel at index [`i`], and turn it into the duty cycle to write in its register.
+    /// The duty cycle is truncated to the whole percent [`duty_to_bytes`] writes before it's clamped, so that the truncation
+    /// can't take it past the limits.
+    fn register_duty(&self, i: usize, duty: f32) -> f32 {
+        assert!(duty >= 0f32);
+        assert!(duty <= 1f32);
+        let (min, max) = percent_limits(self.duty_limits[i].0, self.duty_limits[i].1);
+        let percent = (duty * 1e3).round() as u16 / 10;
+
+        self.apply_invert(i, percent.clamp(min, max) as f32 / 1e2)
+    }
+
+    /// Set how [`digital_write`], [`fade_pwm`], [`fade_pwm_curved`] and [`fade_all`] handle failed writes:
+    /// with [`ErrorPolicy::BestEffort`] they carry on and return `Ok`, e.g. for a status LED that mustn't take a kiosk down.
+    /// So do [`blink_code`], which writes through [`digital_write`], and the fade of [`DropAction::FadeOff`], which runs
+    /// [`fade_all`]. Reads, e.g. the starting duty cycles of [`fade_all`], and every other method stay strict.
+    /// Defaults to [`ErrorPolicy::Strict`].
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.error_policy = policy;
+    }
+
+    /// Set what dropping this instance does to the board. Defaults to [`DropAction::Reset`].
+    pub fn set_drop_action(&mut self, action: DropAction) {
+        self.drop_action = action;
+    }
+
+    /// Swallow the error of a write under [`ErrorPolicy::BestEffort`], with a warning if the `tracing` feature is enabled.
+    fn tolerate(&self, result: Result<(), IoError>) -> Result<(), IoError> {
+        match (self.error_policy, result) {
+            (ErrorPolicy::BestEffort, Err(_err)) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %_err, "ignoring failed write");
+
+                Ok(())
+            }
+            (_, result) => result,
+        }
+    }
+
+    /// Convert between the logical and register duty cycles of the channel at index [`i`]. The conversion is its own inverse.
+    fn apply_invert(&self, i: usize, duty: f32) -> f32 {
+        if self.inverted[i] {
+            1f32 - duty
+        } else {
+            duty
+        }
+    }
+
+    /// Fade the PWM duty cycle of a channel linearly from [`from`] to [`to`] over [`duration`],
+    /// in [`steps`] evenly timed writes. Blocks for the duration of the fade.
+    pub fn fade_pwm(&mut self, channel: Channel, from: f32, to: f32, duration: Duration, steps: u32) -> Result<(), IoError> {
+        self.fade_pwm_curved(channel, from, to, duration, steps, Easing::Linear)
+    }
+
+    /// Same as [`fade_pwm`], but with the progress of the fade shaped by the given [`easing`] curve.
+    pub fn fade_pwm_curved(&mut self, channel: Channel, from: f32, to: f32, duration: Duration, steps: u32, easing: Easing) -> Result<(), IoError> {
+        assert!((0f32..=1f32).contains(&from));
+        assert!((0f32..=1f32).contains(&to));
+
+        if steps == 0 {
+            let result = self.set_pwm_duty(channel, to);
+            return self.tolerate(result);
+        }
+
+        let interval = duration / steps;
+        let result = self.set_pwm_duty(channel, from);
+        self.tolerate(result)?;
+        for step in 1..=steps {
+            thread::sleep(interval);
+
+            let progress = easing.apply(step as f32 / steps as f32);
+            let result = self.set_pwm_duty(channel, (from + (to - from) * progress).clamp(0f32, 1f32));
+            self.tolerate(result)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fade every channel linearly from its current duty cycle to the common [`to`] over [`duration`], in [`steps`] increments,
+    /// e.g. to dim a whole fixture. All four duty cycles are written in a single transaction per step.
+    pub fn fade_all(&mut self, to: f32, duration: Duration, steps: u32) -> Result<(), IoError> {
+        assert!((0f32..=1f32).contains(&to));
+
+        if steps == 0 {
+            let result = self.set_pwm_duties([to; CHANNEL_COUNT]);
+            return self.tolerate(result);
+        }
+
+        let from = self.get_pwm_duties()?;
+        let interval = duration / steps;
+        for step in 1..=steps {
+            thread::sleep(interval);
+
+            let progress = step as f32 / steps as f32;
+            let result = self.set_pwm_duties(from.map(|from| (from + (to - from) * progress).clamp(0f32, 1f32)));
+            self.tolerate(result)?;
+        }
+
+        Ok(())
+    }
+
+    /// Ramp the duty cycle of a channel and the PWM frequency of the board together, linearly from their current values
+    /// to [`target_duty`] and [`target_freq`] over [`ramp`], e.g. to spin up a motor. Both are updated every 20ms.
+    /// The frequency is board-wide: every other channel follows the ramp too.
+    pub fn spin_up(&mut self, channel: Channel, target_duty: f32, target_freq: u16, ramp: Duration) -> Result<(), DfrError> {
+        if !(0f32..=1f32).contains(&target_duty) {
+            return Err(DfrError::InvalidDuty(target_duty));
+        }
+        if !FREQ_RANGE.contains(&target_freq) {
+            return Err(DfrError::InvalidFrequency(target_freq));
+        }
+
+        let from_duty = self.get_pwm_duty(channel)?;
+        let from_freq = self.current_freq()?;
+        if !FREQ_RANGE.contains(&from_freq) {
+            return Err(DfrError::InvalidFrequency(from_freq));
+        }
+
+        let steps = (ramp.as_millis() / 20).max(1) as u32;
+        let interval = ramp / steps;
+        for step in 1..=steps {
+            thread::sleep(interval);
+
+            let progress = step as f32 / steps as f32;
+            let freq = from_freq as f32 + (target_freq as f32 - from_freq as f32) * progress;
+            self.set_pwm_freq(freq.round() as u16)?;
+            self.set_pwm_duty(channel, (from_duty + (target_duty - from_duty) * progress).clamp(0f32, 1f32))?;
+        }
+
+        Ok(())
+    }
+
+    /// Set the PWM frequency for the entire board.
+    /// Valid [`freq`] values are within [`FREQ_RANGE`].
+    pub fn set_pwm_freq(&mut self, freq: u16) -> Result<(), IoError> {
+        assert!(FREQ_RANGE.contains(&freq));
+        let bytes = freq.to_be_bytes();
+
+        self.write_bytes(Register::PwmFreq, &bytes)?;
+        self.frequency = Some(freq);
+        self.freq_configured = true;
+        self.check_servo_frequency(freq);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(freq, "PWM frequency");
+
+        Ok(())
+    }
+
+    /// Set the PWM frequency of the board to the integer nearest to [`hz`], and return it.
+    /// Fails without writing anything if that isn't within [`FREQ_RANGE`].
+    pub fn set_pwm_freq_hz(&mut self, hz: f32) -> Result<u16, DfrError> {
+        let freq = freq_from_hz(hz)?;
+        self.set_pwm_freq(freq)?;
+
+        Ok(freq)
+    }
+
+    /// Set the PWM frequency of the board to [`freq`] for the duration of [`f`], then set it back to what it was.
+    pub fn with_freq<T>(&mut self, freq: u16, f: impl FnOnce(&mut Self) -> T) -> Result<T, DfrError> {
+        if !FREQ_RANGE.contains(&freq) {
+            return Err(DfrError::InvalidFrequency(freq));
+        }
+
+        let original = self.current_freq()?;
+        if !FREQ_RANGE.contains(&original) {
+            return Err(DfrError::InvalidFrequency(original));
+        }
+
+        self.set_pwm_freq(freq)?;
+        let result = f(self);
+        self.set_pwm_freq(original)?;
+
+        Ok(result)
+    }
+
+    /// The PWM frequency last set through this instance, read back from the board if it wasn't set yet.
+    fn current_freq(&mut self) -> Result<u16, IoError> {
+        match self.frequency {
+            Some(freq) if !self.options.minimal => Ok(freq),
+            _ => self.get_pwm_freq(),
+        }
+    }
+
+    /// Walk the PWM frequency of the board from [`from`] to [`to`] (both included, either direction) in increments of [`step`].
+    /// At each frequency, waits [`dwell`] for the load to settle and then calls [`cb`] with the frequency, e.g. to take a measurement.
+    pub fn sweep_freq(&mut self, from: u16, to: u16, step: u16, dwell: Duration, mut cb: impl FnMut(u16)) -> Result<(), DfrError> {
+        for freq in [from, to] {
+            if !FREQ_RANGE.contains(&freq) {
+                return Err(DfrError::InvalidFrequency(freq));
+            }
+        }
+        if step == 0 {
+            return Err(DfrError::InvalidParameter("step must be non-zero"));
+        }
+
+        let mut freq = from;
+        loop {
+            self.set_pwm_freq(freq)?;
+            thread::sleep(dwell);
+            cb(freq);
+
+            if freq == to {
+                return Ok(());
+            }
+            freq = if from <= to {
+                freq.saturating_add(step).min(to)
+            } else {
+                freq.saturating_sub(step).max(to)
+            };
+        }
+    }
+
+    /// Read back the PWM frequency of the board.
+    pub fn get_pwm_freq(&mut self) -> Result<u16, IoError> {
+        let bytes = self.read_bytes(Register::PwmFreq, 2)?;
+        let freq = u16::from_be_bytes([bytes[0], bytes[1]]);
+        self.frequency = Some(freq);
+
+        Ok(freq)
+    }
+
+    /// The period of the PWM signal in microseconds, at the frequency last set through this instance, or read back from the board.
+    /// Fails with [`DfrError::InvalidFrequency`] wrapped in an [`ErrorKind::Other`] error if the board reports a frequency of `0`.
+    pub fn pwm_period_us(&mut self) -> Result<f32, IoError> {
+        let freq = self.current_freq()?;
+        if freq == 0 {
+            return Err(DfrError::InvalidFrequency(freq).into());
+        }
+
+        Ok(1_000_000.0 / freq as f32)
+    }
+
+    /// Get the value of the specified ADC pin, it will return a value between `0` and the full scale set with [`set_adc_bits`],
+    /// [`ADC_FULL_SCALE`] by default. The value is whatever the firmware sends, unless it's above the full scale.
+    ///
+    /// Each channel is two bytes, high byte first: the reference library decodes them as `data[0] << 8 | data[1]`.
+    ///
+    /// The ADC is free-running, so this returns the latest conversion the firmware latched, however recent.
+    /// There's no data-ready flag to wait on: reading faster than the firmware converts returns the same value again.
+    pub fn get_adc_value(&mut self, channel: Channel) -> Result<u16, IoError> {
+        let reg = match channel {
+            Channel::Ch0 => Register::AdcCh0,
+            Channel::Ch1 => Register::AdcCh1,
+            Channel::Ch2 => Register::AdcCh2,
+            Channel::Ch3 => Register::AdcCh3,
+        };
+
+        let mut buf = [0u8; 2];
+        let len = self.read_bytes_into(reg, &mut buf)?;
+        expect_len(buf.len(), len)?;
+
+        adc_from_bytes(buf, self.adc_full_scale)
+    }
+
+    /// Same as [`get_adc_value`], with the channel given by its index.
+    pub fn get_adc_value_by_index(&mut self, idx: u8) -> Result<u16, DfrError> {
+        Ok(self.get_adc_value(Channel::try_from(idx)?)?)
+    }
+
+    /// Same as [`get_adc_value`], with the reading tagged with its channel.
+    pub fn get_adc_reading(&mut self, channel: Channel) -> Result<Reading, IoError> {
+        Ok(Reading {
+            channel,
+            raw: self.get_adc_value(channel)?,
+        })
+    }
+
+    /// Enable the ADC, read each of [`channels`], in order, and put the ADC back in the state it was in, enabled or not, e.g. to save power
+    /// between occasional reads. The state is restored even if a read fails. Readings right after enabling may predate it, as the
+    /// firmware gives no sign of its first conversion.
+    pub fn read_channels_once(&mut self, channels: &[Channel]) -> Result<Vec<u16>, IoError> {
+        // The state was just read from the board: write it as needed whatever the cache says, e.g. after a power cycle.
+        let enabled = self.is_adc_enabled()?;
+        if enabled {
+            self.adc_enabled = Some(true);
+        } else {
+            self.force_enable_adc(true)?;
+        }
+
+        let readings = channels.iter().map(|&channel| self.get_adc_value(channel)).collect();
+        if !enabled {
+            self.force_enable_adc(false)?;
+        }
+
+        readings
+    }
+
+    /// Same as [`get_adc_values`], with each reading tagged with its channel.
+    pub fn get_adc_readings(&mut self) -> Result<[Reading; CHANNEL_COUNT], IoError> {
+        let values = self.get_adc_values()?;
+
+        Ok(Channel::ALL.map(|channel| Reading {
+            channel,
+            raw: values[channel.index()],
+        }))
+    }
+
+    /// Same as [`get_adc_value`], which reads into a buffer on the stack too. Like it, this is a single block read
+    /// with no check of the control register: the ADC must have been enabled with [`enable_adc`] beforehand,
+    /// or the reading is whatever the firmware last latched.
+    pub fn get_adc_value_fast(&mut self, channel: Channel) -> Result<u16, IoError> {
+        self.get_adc_value(channel)
+    }
+
+    /// Read the specified ADC pin [`count`] times back to back, as fast as the bus allows, each reading paired with the
+    /// instant it was taken, e.g. to look at the timing jitter or the shape of a signal afterwards.
+    pub fn capture_burst(&mut self, channel: Channel, count: usize) -> Result<Vec<(Instant, u16)>, IoError> {
+        let mut samples = Vec::with_capacity(count);
+        for _ in 0..count {
+            let value = self.get_adc_value(channel)?;
+            samples.push((Instant::now(), value));
+        }
+
+        Ok(samples)
+    }
+
+    /// Same as [`get_adc_value`], but discard a first read and wait [`settle`] before taking the reading that's returned.
+    ///
+    /// This board shouldn't need it: the firmware latches every channel into its own register, so reading one channel
+    /// after another doesn't switch a mux on the bus side. The wait does guarantee at least one fresh conversion, though.
+    pub fn get_adc_value_settled(&mut self, channel: Channel, settle: Duration) -> Result<u16, IoError> {
+        self.get_adc_value(channel)?;
+        thread::sleep(settle);
+
+        self.get_adc_value(channel)
+    }
+
+    /// Same as [`get_adc_value`], but enable the ADC subsystem first if it isn't, so that the reading isn't stuck at `0`.
+    /// Costs a read of the control register when this instance doesn't know the ADC is enabled, e.g. on the first call,
+    /// and a write on top if it isn't. The reading right after enabling may predate the first conversion.
+    pub fn get_adc_value_auto(&mut self, channel: Channel) -> Result<u16, IoError> {
+        if self.adc_enabled != Some(true) || self.options.minimal {
+            if self.is_adc_enabled()? {
+                self.adc_enabled = Some(true);
+            } else {
+                self.force_enable_adc(true)?;
+            }
+        }
+
+        self.get_adc_value(channel)
+    }
+
+    /// Smooth the readings of [`get_adc_value_smoothed`] on the specified ADC pin with an exponential moving average,
+    /// weighing each new reading by [`alpha`], between `0.0` and `1.0`. Starts over from the next reading.
+    pub fn enable_ema(&mut self, channel: Channel, alpha: f32) {
+        assert!((0f32..=1f32).contains(&alpha));
+
+        self.ema[channel.index()] = Some((alpha, None));
+    }
+
+    /// Read the specified ADC pin once, and return its moving average as enabled with [`enable_ema`]: `alpha * raw + (1 - alpha) * ema`,
+    /// seeded with the first reading. Smooths across calls rather than sampling several times per call.
+    /// Fails with [`DfrError::InvalidParameter`] wrapped in an [`ErrorKind::Other`] error, without reading, if it isn't enabled on the channel.
+    pub fn get_adc_value_smoothed(&mut self, channel: Channel) -> Result<f32, IoError> {
+        let Some((alpha, ema)) = self.ema[channel.index()] else {
+            return Err(DfrError::InvalidParameter("moving average not enabled on the channel").into());
+        };
+        let raw = self.get_adc_value(channel)? as f32;
+
+        let ema = match ema {
+            Some(ema) => alpha * raw + (1f32 - alpha) * ema,
+            None => raw,
+        };
+        self.ema[channel.index()] = Some((alpha, Some(ema)));
+
+        Ok(ema)
+    }
+
+    /// Read the specified ADC pin with [`get_adc_value_settled`] once for each of [`delays`], and return each delay with its reading,
+    /// to find the shortest delay after which readings of a high-impedance source stop drifting.
+    pub fn settling_profile(&mut self, channel: Channel, delays: &[Duration]) -> Result<Vec<(Duration, u16)>, IoError> {
+        delays.iter()
+            .map(|&delay| Ok((delay, self.get_adc_value_settled(channel, delay)?)))
+            .collect()
+    }
+
+    /// Get the values of all ADC pins in a single transaction, indexed by channel.
+    pub fn get_adc_values(&mut self) -> Result<[u16; CHANNEL_COUNT], IoError> {
+        let bytes = self.read_bytes(Register::AdcCh0, 2 * CHANNEL_COUNT as u8)?;
+
+        let mut values = [0u16; CHANNEL_COUNT];
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(2)) {
+            *value = adc_from_bytes([chunk[0], chunk[1]], self.adc_full_scale)?;
+        }
+
+        Ok(values)
+    }
+
+    /// Iterate over all channels paired with the value of their ADC pin.
+    /// Pins are read lazily, one at a time as the iteration proceeds, so readings aren't taken at the same instant.
+    pub fn adc_iter(&mut self) -> impl Iterator<Item = (Channel, Result<u16, IoError>)> + '_ {
+        Channel::all().into_iter().map(move |channel| (channel, self.get_adc_value(channel)))
+    }
+
+    /// Capture the interpreted state of the whole board.
+    pub fn snapshot(&mut self) -> Result<BoardSnapshot, IoError> {
+        Ok(BoardSnapshot {
+            pwm_enabled: self.is_pwm_enabled()?,
+            adc_enabled: self.is_adc_enabled()?,
+            frequency: self.get_pwm_freq()?,
+            duties: self.get_pwm_duties()?,
+            adc_values: self.get_adc_values()?,
+        })
+    }
+
+    /// Read the raw contents of the whole register map, indexed by address, e.g. for diagnostics.
+    /// Contiguous registers are read in blocks, so the sweep takes seven transactions and is cheap enough to call often.
+    /// None of the block reads are confirmed against the firmware: one it doesn't support may fail with [`DfrError::ShortRead`],
+    /// or return bytes that don't match reading the registers one at a time.
+    /// The map isn't captured atomically: ADC readings may update between transactions.
+    pub fn dump_registers(&mut self) -> Result<[u8; REGISTER_MAP_LEN], IoError> {
+        let mut dump = [0; REGISTER_MAP_LEN];
+
+        for (reg, len) in Register::RUNS {
+            let start = reg as usize;
+            let run = &mut dump[start..start + len as usize];
+            if len == 1 {
+                run[0] = self.read_byte(reg)?;
+            } else {
+                let got = self.read_bytes_into(reg, run)?;
+                expect_len(run.len(), got)?;
+            }
+        }
+
+        Ok(dump)
+    }
+
+    /// Capture the identity and the state of the whole board as JSON, e.g. to attach to a bug report.
+    ///
+    /// There are no bus metrics to include: the driver doesn't count transactions, retries or errors. Report the result of
+    /// [`measure_latency`] alongside, or count transactions with [`set_transaction_hook`], if the bus is in question.
+    #[cfg(feature = "serde")]
+    pub fn diagnostics_json(&mut self) -> Result<String, DfrError> {
+        #[derive(serde::Serialize)]
+        struct Diagnostics {
+            #[serde(flatten)]
+            info: BoardInfo,
+            #[serde(flatten)]
+            snapshot: BoardSnapshot,
+        }
+
+        let diagnostics = Diagnostics {
+            info: self.read_info()?,
+            snapshot: self.snapshot()?,
+        };
+
+        serde_json::to_string(&diagnostics).map_err(|err| DfrError::Io(err.into()))
+    }
+
+    /// Reapply the outputs and configuration captured in a snapshot: frequency, duty cycles and enable flags.
+    /// The ADC readings of the snapshot are ignored. Nothing is written if the snapshot holds any out of range value.
+    pub fn restore(&mut self, snap: &BoardSnapshot) -> Result<(), DfrError> {
+        if !FREQ_RANGE.contains(&snap.frequency) {
+            return Err(DfrError::InvalidFrequency(snap.frequency));
+        }
+        if let Some(&duty) = snap.duties.iter().find(|duty| !(0f32..=1f32).contains(*duty)) {
+            return Err(DfrError::InvalidDuty(duty));
+        }
+
+        self.set_pwm_freq(snap.frequency)?;
+        self.set_pwm_duties(snap.duties)?;
+        self.enable_pwm(snap.pwm_enabled)?;
+        self.enable_adc(snap.adc_enabled)?;
+
+        Ok(())
+    }
+
+    /// Get the value of the specified ADC pin as a percentage of the full scale set with [`set_adc_bits`].
+    pub fn adc_percent(&mut self, channel: Channel) -> Result<f32, IoError> {
+        let raw = self.get_adc_value(channel)?;
+
+        Ok(raw as f32 / self.adc_full_scale as f32 * 100.0)
+    }
+
+    /// Sample the specified ADC pin [`samples`] times in a row and report whether every reading was identical,
+    /// as is typical of a shorted or stuck input, where a floating one would wander.
+    /// This is a heuristic: a genuinely stable voltage, especially one at either rail, reads constant too.
+    pub fn is_adc_stuck(&mut self, channel: Channel, samples: u16) -> Result<bool, IoError> {
+        assert!(samples >= 2);
+
+        let first = self.get_adc_value(channel)?;
+        for _ in 1..samples {
+            if self.get_adc_value(channel)? != first {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Read the specified ADC pin as a boolean with hysteresis, like a Schmitt trigger: [`state`] turns `true` once a reading
+    /// exceeds [`high`], and only turns back `false` once one falls below [`low`]. Returns the updated state.
+    pub fn read_schmitt(&mut self, channel: Channel, low: u16, high: u16, state: &mut bool) -> Result<bool, IoError> {
+        assert!(low < high);
+
+        let value = self.get_adc_value(channel)?;
+        if value > high {
+            *state = true;
+        } else if value < low {
+            *state = false;
+        }
+
+        Ok(*state)
+    }
+
+    /// Poll the specified ADC pin every [`interval`] until [`pred`] holds for a reading, and return that reading.
+    /// Returns `None` if it didn't hold by the time [`timeout`] elapsed. The pin is always read at least once,
+    /// and the deadline is checked after every read, so a zero [`interval`] still returns after [`timeout`].
+    pub fn poll_adc_until(&mut self, channel: Channel, interval: Duration, timeout: Duration, pred: impl Fn(u16) -> bool) -> Result<Option<u16>, IoError> {
+        let start = Instant::now();
+
+        loop {
+            let value = self.get_adc_value(channel)?;
+            if pred(value) {
+                return Ok(Some(value));
+            }
+
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Ok(None);
+            }
+
+            thread::sleep(interval.min(timeout - elapsed));
+        }
+    }
+
+    /// Block until a reading of the specified ADC pin differs from the first one taken, polling every [`interval`],
+    /// and return the new reading. Returns `None` if it didn't change by the time [`timeout`] elapsed.
+    ///
+    /// The board has no ready or interrupt pin to wait on: only the I2C lines are wired to the header,
+    /// so polling the bus, as this does on top of [`poll_adc_until`], is the only way to wait for an event.
+    pub fn wait_adc_change(&mut self, channel: Channel, interval: Duration, timeout: Duration) -> Result<Option<u16>, IoError> {
+        let first = self.get_adc_value(channel)?;
+
+        self.poll_adc_until(channel, interval, timeout, |value| value != first)
+    }
+
+    /// Check the output of [`pwm`] is wired back into the ADC pin [`adc`], through an RC filter, by stepping its duty cycle through
+    /// `0.0`, `0.5` and `1.0`, 50ms apart: passes if the readings rise at each step and span at least a quarter of full scale.
+    /// Enables both subsystems, and leaves [`pwm`] at a duty cycle of `0.0`.
+    pub fn loopback_test(&mut self, pwm: Channel, adc: Channel) -> Result<bool, IoError> {
+        self.enable_pwm(true)?;
+        self.enable_adc(true)?;
+
+        let mut readings = [0u16; 3];
+        for (reading, duty) in readings.iter_mut().zip([0f32, 0.5, 1f32]) {
+            self.set_pwm_duty(pwm, duty)?;
+            thread::sleep(Duration::from_millis(50));
+            *reading = self.get_adc_value(adc)?;
+        }
+        self.set_pwm_duty(pwm, 0f32)?;
+
+        Ok(readings[0] < readings[1]
+            && readings[1] < readings[2]
+            && readings[2] - readings[0] >= self.adc_full_scale / 4)
+    }
+
+    /// Run [`loopback_test`] on every (PWM, ADC) pair, e.g. to check a whole board on a test jig, and return whether each PWM channel passed.
+    /// All the PWM channels of the pairs are left at a duty cycle of `0.0`, even if a test fails on an error.
+    pub fn loopback_test_all(&mut self, pairs: &[(Channel, Channel)]) -> Result<Vec<(Channel, bool)>, IoError> {
+        let mut results = Vec::with_capacity(pairs.len());
+        let mut outcome = Ok(());
+        for &(pwm, adc) in pairs {
+            match self.loopback_test(pwm, adc) {
+                Ok(passed) => results.push((pwm, passed)),
+                Err(err) => {
+                    outcome = Err(err);
+                    break;
+                }
+            }
+        }
+
+        for &(pwm, _) in pairs {
+            self.set_pwm_duty(pwm, 0f32)?;
+        }
+        outcome?;
+
+        Ok(results)
+    }
+
+    /// Turn the board into a stream yielding a reading of the specified ADC pin every [`interval`], the first one immediately.
+    /// Ticks missed by a slow consumer are delayed rather than bursted. Each read is a short blocking I/O on the polling task.
+    /// The stream never ends by itself; dropping it drops the board, which resets it as usual.
+    /// Must be called from within a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn adc_stream(self, channel: Channel, interval: Duration) -> impl futures_core::Stream<Item = Result<u16, IoError>> {
+        stream::AdcStream::new(self, channel, interval)
+    }
+
+    /// Set the resolution of the ADC, between `8` and `16` bits, for firmware that doesn't report 10-bit readings.
+    /// The full scale of readings, `2^bits - 1`, scales [`get_adc_voltage`], [`adc_percent`] and the like,
+    /// and bounds [`DfrError::ImplausibleReading`]. Defaults to `10`.
+    pub fn set_adc_bits(&mut self, bits: u8) {
+        assert!((8..=16).contains(&bits));
+
+        self.adc_full_scale = ((1u32 << bits) - 1) as u16;
+    }
+
+    /// Highest value the ADC can report, as set by [`set_adc_bits`].
+    pub fn adc_full_scale(&self) -> u16 {
+        self.adc_full_scale
+    }
+
+    /// Set the reference voltage of the ADC, in millivolts, i.e. the voltage a reading at full scale stands for. Defaults to 5000.
+    pub fn set_adc_reference_mv(&mut self, mv: u16) {
+        self.adc_reference_mv = mv;
+    }
+
+    /// Set the calibration of the specified ADC pin, applied by [`get_adc_voltage`].
+    pub fn set_adc_calibration(&mut self, channel: Channel, calibration: AdcCalibration) {
+        self.calibration[channel.index()] = calibration;
+    }
+
+    /// Get the voltage on the specified ADC pin, scaled to the reference voltage and corrected by the calibration of the pin.
+    pub fn get_adc_voltage(&mut self, channel: Channel) -> Result<f32, IoError> {
+        Ok(self.get_adc_both(channel)?.1)
+    }
+
+    /// Get both the raw value of the specified ADC pin and the voltage it converts to as with [`get_adc_voltage`], from a single read.
+    pub fn get_adc_both(&mut self, channel: Channel) -> Result<(u16, f32), IoError> {
+        let calibration = self.calibration[channel.index()];
+        let raw = self.get_adc_value(channel)?;
+        let measured = self.uncalibrated_voltage(raw);
+
+        Ok((raw, calibration.gain * measured + calibration.offset))
+    }
+
+    /// Calibrate the specified ADC pin against two known voltages, guiding an operator through it, and store the result as with
+    /// [`set_adc_calibration`]. For each of a low and a high point, [`prompt`] is given an instruction to show the operator,
+    /// then [`reference`] is called to block until the operator is done, and return the true voltage on the pin, e.g. as read off a multimeter.
+    /// The pin is read right after. Fails with [`DfrError::InvalidParameter`] if both points read the same, leaving the calibration as it was.
+    pub fn calibrate_interactive(&mut self, channel: Channel, mut prompt: impl FnMut(&str), mut reference: impl FnMut() -> f32) -> Result<(), DfrError> {
+        let mut points = [(0f32, 0f32); 2];
+
+        for (point, level) in points.iter_mut().zip(["low", "high"]) {
+            prompt(&format!("Apply a known {level} voltage to the ADC pin of {channel:?}, then enter it"));
+            let actual = reference();
+            let raw = self.get_adc_value(channel)?;
+
+            *point = (self.uncalibrated_voltage(raw), actual);
+        }
+
+        let [(measured_low, actual_low), (measured_high, actual_high)] = points;
+        let gain = (actual_high - actual_low) / (measured_high - measured_low);
+        if !gain.is_finite() {
+            return Err(DfrError::InvalidParameter("the two calibration points read the same"));
+        }
+
+        self.set_adc_calibration(channel, AdcCalibration {
+            gain,
+            offset: actual_low - gain * measured_low,
+        });
+        prompt("Calibration done");
+
+        Ok(())
+    }
+
+    /// Convert a raw ADC reading to volts, scaled to the reference voltage but without the calibration of the pin.
+    fn uncalibrated_voltage(&self, raw: u16) -> f32 {
+        raw as f32 / self.adc_full_scale as f32 * self.adc_reference_mv as f32 / 1e3
+    }
+
+    /// Set the duty cycle of [`pwm`] to deliver the average voltage [`target_fraction`] would at a supply of [`nominal_v`] volts,
+    /// scaling it by the sag of the supply voltage read on [`vsense`] with [`get_adc_voltage`]. Clamped to `0.0..=1.0`.
+    /// The power into a resistive load goes with the square of the voltage, so it isn't held: a sagging supply still delivers less.
+    pub fn set_compensated_duty(&mut self, pwm: Channel, target_fraction: f32, vsense: Channel, nominal_v: f32) -> Result<(), IoError> {
+        assert!((0f32..=1f32).contains(&target_fraction));
+        assert!(nominal_v > 0f32);
+
+        let supply = self.get_adc_voltage(vsense)?.max(f32::MIN_POSITIVE);
+        let duty = (target_fraction * nominal_v / supply).clamp(0f32, 1f32);
+
+        self.set_pwm_duty(pwm, duty)
+    }
+
+    /// Take one step of a proportional controller dimming [`led`] to keep the light sensor on [`sensor`] reading [`target`]:
+    /// the duty cycle moves by [`gain`] times the error, as a fraction of the full scale of the ADC, clamped to `0.0..=1.0`.
+    /// A brighter LED is assumed to raise the reading. Call it in a loop, at the rate the sensor settles.
+    pub fn auto_dim(&mut self, led: Channel, sensor: Channel, target: u16, gain: f32) -> Result<(), IoError> {
+        let reading = self.get_adc_value(sensor)?;
+        let error = (target as f32 - reading as f32) / self.adc_full_scale as f32;
+
+        let duty = self.get_pwm_duty(led)?;
+        self.set_pwm_duty(led, (duty + gain * error).clamp(0f32, 1f32))
+    }
+
+    /// Map the voltage on the specified ADC pin to the quantity measured by the sensor on it, as `slope * voltage + intercept`, in [`unit`].
+    /// Defaults to the voltage itself, in volts.
+    pub fn set_channel_mapping(&mut self, channel: Channel, slope: f32, intercept: f32, unit: &'static str) {
+        self.mappings[channel.index()] = (slope, intercept, unit);
+    }
+
+    /// Get the quantity measured on the specified ADC pin along with its unit, as mapped by [`set_channel_mapping`] from [`get_adc_voltage`].
+    pub fn read_mapped(&mut self, channel: Channel) -> Result<(f32, &'static str), IoError> {
+        let (slope, intercept, unit) = self.mappings[channel.index()];
+        let voltage = self.get_adc_voltage(channel)?;
+
+        Ok((slope * voltage + intercept, unit))
+    }
+
+    /// Characterize the sensor on the specified ADC pin with a lookup table of `(raw, value)` points, used by [`read_lut`].
+    /// The points are sorted by raw reading; the table can't be empty.
+    pub fn set_adc_lut(&mut self, channel: Channel, mut table: Vec<(u16, f32)>) {
+        assert!(!table.is_empty());
+        table.sort_by_key(|&(raw, _)| raw);
+
+        self.luts[channel.index()] = table;
+    }
+
+    /// Get the quantity measured on the specified ADC pin, interpolated linearly between the nearest points of the table
+    /// set with [`set_adc_lut`]. Readings outside of the table clamp to its first or last value.
+    /// Fails with [`DfrError::InvalidParameter`] wrapped in an [`ErrorKind::Other`] error, without reading, if the channel has no table.
+    pub fn read_lut(&mut self, channel: Channel) -> Result<f32, IoError> {
+        if self.luts[channel.index()].is_empty() {
+            return Err(DfrError::InvalidParameter("no lookup table set on the channel").into());
+        }
+        let raw = self.get_adc_value(channel)?;
+        let table = &self.luts[channel.index()];
+
+        let upper = table.partition_point(|&(point, _)| point < raw);
+        if upper == 0 {
+            return Ok(table[0].1);
+        }
+        if upper == table.len() {
+            return Ok(table[table.len() - 1].1);
+        }
+
+        let (x0, y0) = table[upper - 1];
+        let (x1, y1) = table[upper];
+        let t = (raw - x0) as f32 / (x1 - x0) as f32;
+
+        Ok(y0 + (y1 - y0) * t)
+    }
+
+    /// Get the voltage on the specified ADC pin in millivolts, using integer math only.
+    pub fn get_adc_millivolts(&mut self, channel: Channel) -> Result<u16, IoError> {
+        let raw = self.get_adc_value(channel)? as u32;
+
+        Ok((raw * self.adc_reference_mv as u32 / self.adc_full_scale as u32) as u16)
+    }
+
+    /// Sample the specified ADC pin [`samples`] times in a row and return the root mean square of the readings.
+    pub fn get_adc_rms(&mut self, channel: Channel, samples: u16) -> Result<f32, IoError> {
+        assert!(samples >= 1);
+
+        let mut sum_sq = 0f64;
+        for _ in 0..samples {
+            let value = self.get_adc_value(channel)? as f64;
+            sum_sq += value * value;
+        }
+
+        Ok((sum_sq / samples as f64).sqrt() as f32)
+    }
+
+    /// Sample the specified ADC pin [`samples`] times in a row, discard the lowest and highest [`trim_fraction`] of the readings,
+    /// and return the mean of the rest, rounded. Valid [`trim_fraction`] values are from `0.0`, a plain mean, up to but excluding `0.5`.
+    pub fn get_adc_value_trimmed_mean(&mut self, channel: Channel, samples: u16, trim_fraction: f32) -> Result<u16, IoError> {
+        assert!(samples >= 1);
+        assert!((0f32..0.5).contains(&trim_fraction));
+
+        let mut readings = (0..samples)
+            .map(|_| self.get_adc_value(channel))
+            .collect::<Result<Vec<_>, _>>()?;
+        readings.sort_unstable();
+
+        let trim = (samples as f32 * trim_fraction) as usize;
+        let kept = &readings[trim..readings.len() - trim];
+        let sum: u32 = kept.iter().map(|&value| value as u32).sum();
+
+        Ok(((sum as f32 / kept.len() as f32).round()) as u16)
+    }
+}
+
+/// Lock the shared bus handle. The handle holds no invariants a panicking holder could break, so poisoning is ignored.
+fn lock_bus(dev: &Mutex<BusHandle>) -> MutexGuard<'_, BusHandle> {
+    dev.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Disable PWM and zero every duty register, directly on the bus so that the watchdog thread can, without a [`DfrIoHat`].
+/// Writes the same as [`DfrIoHat::all_off`].
+fn outputs_off(dev: &mut dyn Bus) -> Result<(), IoError> {
+    dev.write_byte_data(Register::PwmCtrl as u8, 0x00)?;
+    dev.write_i2c_block_data(Register::PwmDuty0 as u8, &[0x00; 2 * CHANNEL_COUNT])?;
+
+    Ok(())
+}
+
+/// List the addresses at which a board identifies itself on the bus of the handle, leaving it on the last address probed.
+/// Sweeps `0x08..=0x77` as `i2cdetect` does, leaving out the addresses I2C reserves. Addresses that can't be selected,
+/// e.g. because a kernel driver holds them, and addresses that don't acknowledge a quick write are skipped,
+/// without reading the identity registers of whatever else is on the bus.
+fn scan_handle(dev: &mut dyn Bus) -> Vec<u8> {
+    let mut found = vec![];
+    for addr in 0x08..=0x77 {
+        if dev.set_slave_address(addr).is_err() || !dev.quick_probe() {
+            continue;
+        }
+
+        let identity = dev.read_byte_data(Register::PID as u8)
+            .and_then(|pid| Ok((pid, dev.read_byte_data(Register::VID as u8)?)));
+        if let Ok((pid, vid)) = identity {
+            if pid == DEFAULT_PID && vid == DEFAULT_VID {
+                found.push(addr);
+            }
+        }
+    }
+
+    found
+}
+
+/// Fail with [`DfrError::ShortRead`] if a block read returned fewer bytes than expected.
+fn expect_len(expected: usize, got: usize) -> Result<(), IoError> {
+    if got < expected {
+        return Err(IoError::new(ErrorKind::UnexpectedEof, DfrError::ShortRead {
+            expected: expected as u8,
+            got: got as u8,
+        }));
+    }
+
+    Ok(())
+}
+
+/// Decode the two ADC register bytes, failing with [`DfrError::ImplausibleReading`] above [`full_scale`].
+/// Big-endian, as in the reference library, e.g. `[0x03, 0xFF]` is `1023`.
+fn adc_from_bytes(bytes: [u8; 2], full_scale: u16) -> Result<u16, IoError> {
+    let value = u16::from_be_bytes(bytes);
+    if value > full_scale {
+        return Err(IoError::new(ErrorKind::InvalidData, DfrError::ImplausibleReading { value }));
+    }
+
+    Ok(value)
+}
+
+/// Encode a duty cycle between `0.000` and `1.000` into the two duty register bytes.
+fn duty_to_bytes(duty: f32) -> [u8; 2] {
+    assert!(duty >= 0f32);
+    assert!(duty <= 1f32);
+    // Pin the rails to the exact full-off and full-on encodings, whatever the float arithmetic below makes of them.
+    if duty == 0f32 {
+        return [0, 0];
+    }
+    if duty == 1f32 {
+        return [100, 0];
+    }
+    // Round to the tenths of a percent the registers hold before truncating to whole percents as the reference library does,
+    // so that float error doesn't cost a percent: `0.53f32 * 1e2` is `52.999996`.
+    let duty = (duty * 1e3).round() as u16 / 10;
+
+    [duty as u8, ((duty * 10) % 10) as u8] // This is from the reference library and I'm not gonna question it
+}
+
+/// Round duty limits inwards to the whole percents [`duty_to_bytes`] writes, going through tenths of a percent as it does.
+fn percent_limits(min: f32, max: f32) -> (u16, u16) {
+    (((min * 1e3).round() as u16).div_ceil(10), (max * 1e3).round() as u16 / 10)
+}
+
+/// Round a frequency in hertz to the nearest integer, halves away from zero, failing with [`DfrError::InvalidFrequency`]
+/// if that isn't within [`FREQ_RANGE`] (or if [`hz`] is NaN).
+fn freq_from_hz(hz: f32) -> Result<u16, DfrError> {
+    let freq = hz.round() as u16;
+    if !(*FREQ_RANGE.start() as f32..=*FREQ_RANGE.end() as f32).contains(&hz.round()) {
+        return Err(DfrError::InvalidFrequency(freq));
+    }
+
+    Ok(freq)
+}
+
+/// Encode a duty cycle in tenths of a percent into the two duty register bytes (percent, tenths of a percent).
+fn permille_to_bytes(permille: u16) -> [u8; 2] {
+    [(permille / 10) as u8, (permille % 10) as u8]
+}
+
+/// Decode the two duty register bytes (percent, tenths of a percent) back into a fraction.
+fn duty_from_bytes(bytes: [u8; 2]) -> f32 {
+    (bytes[0] as f32 * 10.0 + bytes[1] as f32) / 1e3
+}
+
+/// Resets the board as with [`DfrIoHat::reset`] on the way out, after fading the outputs off if so set with [`DfrIoHat::set_drop_action`],
+/// unless nothing was written since the last [`DfrIoHat::reset`] (or [`DfrIoHat::all_off`] with the ADC disabled).
+/// Strictly best-effort: errors, e.g. from a board that was unplugged, are swallowed.
+impl Drop for DfrIoHat {
+    fn drop(&mut self) {
+        if !self.reset_done {
+            if let DropAction::FadeOff { duration } = self.drop_action {
+                let duration = duration.min(MAX_DROP_FADE);
+                let _ = self.fade_all(0f32, duration, (duration.as_millis() / 20) as u32);
+            }
+            let _result = self.reset();
+            #[cfg(feature = "tracing")]
+            if let Err(err) = _result {
+                tracing::debug!(%err, "reset on drop failed");
+            }
+        }
+        // Only let go of the board once it's been reset.
+        drop(self.lock_file.take());
+    }
+}
+
+impl Display for DfrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DfrError::NoResponse { source } => write!(f, "No response from the device, check the wiring and address: {}", source),
+            DfrError::WrongDevice { pid, vid } => write!(f, "Unexpected device with PID {:#04X} and VID {:#04X}.", pid, vid),
+            DfrError::SoftVersion => write!(f, "Firmware/software version mismatch."),
+            DfrError::NotFound => write!(f, "No board found on the bus."),
+            DfrError::InvalidChannel(idx) => write!(f, "Invalid channel {}, expected 0 to {}.", idx, CHANNEL_COUNT - 1),
+            DfrError::InvalidParameter(msg) => write!(f, "Invalid parameter: {}.", msg),
+            DfrError::InvalidFrequency(freq) => write!(f, "Invalid PWM frequency {}, expected {} to {}.", freq, FREQ_RANGE.start(), FREQ_RANGE.end()),
+            DfrError::InvalidDuty(duty) => write!(f, "Invalid PWM duty cycle {}, expected 0.0 to 1.0.", duty),
+            DfrError::PermissionDenied { path, .. } => write!(f, "Permission denied opening {}, add the user to the `i2c` group or check the udev rules of the device.", path.display()),
+            DfrError::BusNotFound { bus, path } => write!(f, "No I2C bus {}: {} doesn't exist, enable I2C (e.g. with `raspi-config`) or load the `i2c-dev` module.", bus, path.display()),
+            DfrError::AlreadyInUse { path } => write!(f, "Board already in use, lock {} is held.", path.display()),
+            DfrError::AddressChangeFailed { requested } => write!(f, "The board didn't answer at its new address {:#04X}.", requested),
+            DfrError::VerifyMismatch { wrote, read } => write!(f, "Verification failed: wrote {:#04X}, read back {:#04X}.", wrote, read),
+            DfrError::ShortRead { expected, got } => write!(f, "Short read: expected {} bytes, got {}.", expected, got),
+            DfrError::ImplausibleReading { value } => write!(f, "Implausible ADC reading {}, above full scale: likely a bus error.", value),
+            DfrError::Io(err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl Error for DfrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DfrError::NoResponse { source } => Some(source),
+            DfrError::PermissionDenied { source, .. } => Some(source),
+            DfrError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<IoError> for DfrError {
+    /// Unwraps a [`DfrError`] carried by the I/O error, if any.
+    fn from(err: IoError) -> Self {
+        if err.get_ref().is_some_and(|inner| inner.is::<DfrError>()) {
+            return *err.into_inner().unwrap().downcast::<DfrError>().unwrap();
+        }
+
+        DfrError::Io(err)
+    }
+}
+
+impl From<DfrError> for IoError {
+    /// Wraps non-I/O errors as [`ErrorKind::Other`], or the kind they were raised with for [`DfrError::ShortRead`],
+    /// [`DfrError::ImplausibleReading`], [`DfrError::PermissionDenied`] and [`DfrError::BusNotFound`], keeping the original error as the payload so that converting back yields it again.
+    fn from(err: DfrError) -> Self {
+        match err {
+            DfrError::Io(err) => err,
+            DfrError::PermissionDenied { .. } => IoError::new(ErrorKind::PermissionDenied, err),
+            DfrError::BusNotFound { .. } => IoError::new(ErrorKind::NotFound, err),
+            DfrError::ShortRead { .. } => IoError::new(ErrorKind::UnexpectedEof, err),
+            DfrError::ImplausibleReading { .. } => IoError::new(ErrorKind::InvalidData, err),
+            err => IoError::other(err),
+        }
+    }
+}
+
+impl Register {
+    /// Runs of contiguous registers, with the number of bytes in each, covering the whole map in order, for [`DfrIoHat::dump_registers`].
+    ///
+    /// None of the multi-byte block reads are confirmed against the firmware or on hardware. The reference library reads the PID
+    /// and VID with two single byte reads, one 2-byte ADC channel at a time, and never reads the duty registers back.
+    /// The frequency, duty and ADC runs are the block reads the rest of this driver already makes; the identity run is new.
+    /// Runs are split at every control register, so a read never spans from one group of registers into the next.
+    const RUNS: [(Register, u8); 7] = [
+        (Register::SlaveAddr, 1),
+        (Register::PID, 2),
+        (Register::PwmCtrl, 1),
+        (Register::PwmFreq, 2),
+        (Register::PwmDuty0, 2 * CHANNEL_COUNT as u8),
+        (Register::AdcCtrl, 1),
+        (Register::AdcCh0, 2 * CHANNEL_COUNT as u8),
+    ];
+
+    /// Transaction to read and write the register with: byte-data for single byte registers, I2C block for every wider one.
+    /// Derived from the register widths and transfers of the reference library, not confirmed on hardware.
+    fn transfer_kind(&self) -> TransferKind {
+        match self {
+            Register::SlaveAddr | Register::PwmCtrl | Register::AdcCtrl => TransferKind::Byte,
+            _ => TransferKind::I2cBlock,
+        }
+    }
+}
+
+impl Channel {
+    /// All the channels, in order.
+    pub const ALL: [Channel; CHANNEL_COUNT] = [
+        Channel::Ch0,
+        Channel::Ch1,
+        Channel::Ch2,
+        Channel::Ch3,
+    ];
+
+    /// Return an iterator over all the channels
+    pub fn all() -> [Channel; CHANNEL_COUNT] {
+        Self::ALL
+    }
+
+    /// Position of the channel on the board, between `0` and [`CHANNEL_COUNT`] exclusive.
+    pub fn index(&self) -> usize {
+        match self {
+            Channel::Ch0 => 0,
+            Channel::Ch1 => 1,
+            Channel::Ch2 => 2,
+            Channel::Ch3 => 3,
+        }
+    }
+}
+
+impl TryFrom<u8> for Channel {
+    type Error = DfrError;
+
+    /// Fails with [`DfrError::InvalidChannel`] unless [`idx`] is below [`CHANNEL_COUNT`].
+    fn try_from(idx: u8) -> Result<Self, Self::Error> {
+        Channel::ALL.get(idx as usize).copied().ok_or(DfrError::InvalidChannel(idx))
+    }
+}
+
+impl Easing {
+    /// Map the linear progress of a fade (between `0.0` and `1.0`) onto this curve.
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => if t < 0.5 {
+                2.0 * t * t
+            } else {
+                1.0 - (2.0 - 2.0 * t) * (2.0 - 2.0 * t) / 2.0
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::bus::MockBus;
+    use crate::*;
+
+    /// Open a board on the mock, as from a real handle.
+    fn open_mock(mock: &MockBus) -> DfrIoHat {
+        DfrIoHat::connect(Box::new(mock.clone()), DfrIoHat::builder()).unwrap()
+    }
+
+    /// Record the registers written from now on, in order.
+    fn record_writes(hat: &mut DfrIoHat) -> Arc<Mutex<Vec<u8>>> {
+        let writes = Arc::new(Mutex::new(vec![]));
+        let hook_writes = writes.clone();
+        hat.set_transaction_hook(move |transaction| {
+            if transaction.kind == TransactionKind::Write {
+                hook_writes.lock().unwrap().push(transaction.register);
+            }
+        });
+
+        writes
+    }
+
+    #[test]
+    fn pwm_duties_round_trip() {
+        let mut hat = open_mock(&MockBus::new());
+        let duties = [0f32, 0.25, 0.5, 1.0];
+
+        hat.set_pwm_duties(duties).unwrap();
+
+        assert_eq!(hat.get_pwm_duties().unwrap(), duties);
+    }
+
+    #[test]
+    fn duties_are_written_without_a_count_byte() {
+        let mock = MockBus::new();
+        let mut hat = open_mock(&mock);
+        let writes = Arc::new(Mutex::new(vec![]));
+        let hook_writes = writes.clone();
+        hat.set_transaction_hook(move |transaction| hook_writes.lock().unwrap().push(transaction.bytes.to_vec()));
+
+        hat.set_pwm_duty(Channel::Ch1, 0.5).unwrap();
+
+        assert_eq!(*writes.lock().unwrap(), [vec![50, 0]]);
+        assert_eq!(mock.regs.lock().unwrap()[Register::PwmDuty1 as usize..][..3], [50, 0, 0]);
+    }
+
+    #[test]
+    fn short_read_is_reported() {
+        let mut mock = MockBus::new();
+        mock.block_limit = Some(1);
+        let mut hat = open_mock(&mock);
+
+        let err = DfrError::from(hat.get_adc_value(Channel::Ch0).unwrap_err());
+
+        assert!(matches!(err, DfrError::ShortRead { expected: 2, got: 1 }));
+    }
+
+    #[test]
+    fn register_map_matches_the_reference_library() {
+        assert_eq!(Register::VID as u8, 0x02);
+        assert_eq!(Register::PwmDuty3 as u8, 0x0C);
+        assert_eq!(Register::AdcCh3 as u8, 0x15);
+        assert_eq!(REGISTER_MAP_LEN, 0x17);
+        assert_eq!((DEFAULT_PID, DEFAULT_VID), (0xDF, 0x10));
+    }
+
+    #[test]
+    fn adc_bytes_are_big_endian() {
+        assert_eq!(adc_from_bytes([0x00, 0x01], ADC_FULL_SCALE).unwrap(), 1);
+        assert_eq!(adc_from_bytes([0x03, 0xFF], ADC_FULL_SCALE).unwrap(), 1023);
+
+        let err = DfrError::from(adc_from_bytes([0x04, 0x00], ADC_FULL_SCALE).unwrap_err());
+        assert!(matches!(err, DfrError::ImplausibleReading { value: 1024 }));
+    }
+
+    #[test]
+    fn duty_to_bytes_pins_the_rails() {
+        assert_eq!(duty_to_bytes(0.0), [0, 0]);
+        assert_eq!(duty_to_bytes(1.0), [100, 0]);
+    }
+
+    #[test]
+    fn duty_to_bytes_truncates_to_whole_percents() {
+        assert_eq!(duty_to_bytes(0.53), [53, 0]);
+        assert_eq!(duty_to_bytes(0.59), [59, 0]);
+        assert_eq!(duty_to_bytes(0.535), [53, 0]);
+        assert_eq!(duty_to_bytes(0.999), [99, 0]);
+    }
+
+    #[test]
+    fn whole_percents_encode_as_the_reference_library() {
+        for percent in 0..=100u8 {
+            assert_eq!(permille_to_bytes(percent as u16 * 10), [percent, 0]);
+        }
+    }
+
+    #[test]
+    fn whole_percents_are_representable() {
+        let hat = open_mock(&MockBus::new());
+
+        for percent in 0..=100u8 {
+            let duty = percent as f32 / 1e2;
+            assert!(hat.is_duty_representable(duty), "{duty}");
+            assert_eq!(hat.nearest_representable_duty(duty), duty);
+        }
+    }
+
+    #[test]
+    fn other_duties_round_down_to_whole_percents() {
+        let hat = open_mock(&MockBus::new());
+
+        for (duty, nearest) in [(0.525, 0.52), (0.539, 0.53), (0.5396, 0.54), (0.999, 0.99), (-0.1, 0.0), (1.5, 1.0), (f32::NAN, 0.0)] {
+            assert!(!hat.is_duty_representable(duty), "{duty}");
+            assert_eq!(hat.nearest_representable_duty(duty), nearest, "{duty}");
+        }
+    }
+
+    #[test]
+    fn freq_from_hz_rounds_at_the_range_boundaries() {
+        assert!(matches!(freq_from_hz(0.49), Err(DfrError::InvalidFrequency(0))));
+        assert!(matches!(freq_from_hz(0.5), Ok(1)));
+        assert!(matches!(freq_from_hz(999.5), Ok(1000)));
+        assert!(matches!(freq_from_hz(1000.49), Ok(1000)));
+        assert!(matches!(freq_from_hz(1000.5), Err(DfrError::InvalidFrequency(1001))));
+        assert!(matches!(freq_from_hz(f32::NAN), Err(DfrError::InvalidFrequency(_))));
+    }
+
+    #[test]
+    fn reset_takes_three_transactions() {
+        let mut hat = open_mock(&MockBus::new());
+        let writes = record_writes(&mut hat);
+
+        hat.reset().unwrap();
+
+        assert_eq!(*writes.lock().unwrap(), [Register::PwmCtrl as u8, Register::PwmDuty0 as u8, Register::AdcCtrl as u8]);
+    }
+
+    #[test]
+    fn enabling_again_writes_nothing() {
+        let mut hat = open_mock(&MockBus::new());
+        let writes = record_writes(&mut hat);
+
+        hat.enable_pwm(true).unwrap();
+        hat.enable_pwm(true).unwrap();
+        hat.enable_adc(true).unwrap();
+        hat.enable_adc(true).unwrap();
+
+        assert_eq!(*writes.lock().unwrap(), [Register::PwmCtrl as u8, Register::AdcCtrl as u8]);
+    }
+
+    #[test]
+    fn enabling_after_a_watchdog_trip_writes() {
+        let mock = MockBus::new();
+        let mut hat = open_mock(&mock);
+        hat.enable_pwm(true).unwrap();
+        let writes = record_writes(&mut hat);
+
+        hat.start_watchdog(Duration::from_millis(1)).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert!(hat.watchdog_tripped());
+        assert_eq!(mock.regs.lock().unwrap()[Register::PwmCtrl as usize], 0x00);
+
+        hat.enable_pwm(true).unwrap();
+
+        assert_eq!(*writes.lock().unwrap(), [Register::PwmCtrl as u8]);
+        assert_eq!(mock.regs.lock().unwrap()[Register::PwmCtrl as usize], 0x01);
+    }
+
+    #[test]
+    fn reset_outputs_only_leaves_inverted_channels_off() {
+        let mock = MockBus::new();
+        let mut hat = open_mock(&mock);
+        hat.enable_adc(true).unwrap();
+        hat.set_pwm_invert(Channel::Ch1, true);
+        hat.set_pwm_duty(Channel::Ch1, 0.5).unwrap();
+
+        hat.reset_outputs_only().unwrap();
+
+        assert_eq!(hat.get_pwm_duties().unwrap(), [0f32; CHANNEL_COUNT]);
+        assert_eq!(mock.regs.lock().unwrap()[Register::PwmDuty1 as usize], 100);
+        assert!(hat.is_adc_enabled().unwrap());
+        assert!(!hat.is_pwm_enabled().unwrap());
+    }
+
+    #[test]
+    fn commit_batch_keeps_writes_that_bypassed_it() {
+        let mock = MockBus::new();
+        let mut hat = open_mock(&mock);
+
+        hat.begin_batch().unwrap();
+        hat.set_pwm_duty(Channel::Ch0, 0.25).unwrap();
+        hat.set_pwm_duty_permille(Channel::Ch1, 505).unwrap();
+        hat.commit_batch().unwrap();
+
+        let regs = mock.regs.lock().unwrap();
+        assert_eq!(regs[Register::PwmDuty0 as usize..][..2], [25, 0]);
+        assert_eq!(regs[Register::PwmDuty1 as usize..][..2], [50, 5]);
+    }
+
+    #[test]
+    fn smoothing_needs_enabling() {
+        let mock = MockBus::new();
+        let mut hat = open_mock(&mock);
+        mock.regs.lock().unwrap()[Register::AdcCh0 as usize + 1] = 100;
+
+        let err = DfrError::from(hat.get_adc_value_smoothed(Channel::Ch0).unwrap_err());
+        assert!(matches!(err, DfrError::InvalidParameter(_)));
+
+        hat.enable_ema(Channel::Ch0, 0.5);
+        assert_eq!(hat.get_adc_value_smoothed(Channel::Ch0).unwrap(), 100.0);
+        mock.regs.lock().unwrap()[Register::AdcCh0 as usize + 1] = 200;
+        assert_eq!(hat.get_adc_value_smoothed(Channel::Ch0).unwrap(), 150.0);
+    }
+
+    #[test]
+    fn scan_skips_busy_and_reserved_addresses() {
+        let mut mock = MockBus::new();
+        mock.busy = vec![0x68];
+
+        let found = scan_handle(&mut mock);
+
+        assert_eq!(found, (0x08..=0x77).filter(|&addr| addr != 0x68).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn lookup_needs_a_table() {
+        let mock = MockBus::new();
+        let mut hat = open_mock(&mock);
+        mock.regs.lock().unwrap()[Register::AdcCh0 as usize + 1] = 150;
+
+        let err = DfrError::from(hat.read_lut(Channel::Ch0).unwrap_err());
+        assert!(matches!(err, DfrError::InvalidParameter(_)));
+
+        hat.set_adc_lut(Channel::Ch0, vec![(200, 2.0), (100, 1.0)]);
+        assert_eq!(hat.read_lut(Channel::Ch0).unwrap(), 1.5);
+    }
+
+    #[test]
+    fn all_off_is_seen_by_the_hook() {
+        let mut hat = open_mock(&MockBus::new());
+        let writes = record_writes(&mut hat);
+
+        hat.all_off().unwrap();
+
+        assert_eq!(*writes.lock().unwrap(), [Register::PwmCtrl as u8, Register::PwmDuty0 as u8]);
+    }
+
+    #[test]
+    fn read_channels_once_trusts_the_board_over_the_cache() {
+        let mock = MockBus::new();
+        let mut hat = open_mock(&mock);
+        hat.enable_adc(true).unwrap();
+        // Power cycled behind the driver's back.
+        mock.regs.lock().unwrap()[Register::AdcCtrl as usize] = 0x00;
+        let writes = record_writes(&mut hat);
+
+        hat.read_channels_once(&[Channel::Ch0]).unwrap();
+
+        assert_eq!(*writes.lock().unwrap(), [Register::AdcCtrl as u8, Register::AdcCtrl as u8]);
+        assert_eq!(mock.regs.lock().unwrap()[Register::AdcCtrl as usize], 0x00);
+    }
+
+    #[test]
+    fn duty_limits_survive_truncation() {
+        let mock = MockBus::new();
+        let mut hat = open_mock(&mock);
+        hat.set_duty_limits(Channel::Ch0, 0.255, 0.745);
+
+        hat.set_pwm_duty(Channel::Ch0, 0.0).unwrap();
+        assert_eq!(hat.get_pwm_duty(Channel::Ch0).unwrap(), 0.26);
+        hat.set_pwm_duty(Channel::Ch0, 1.0).unwrap();
+        assert_eq!(hat.get_pwm_duty(Channel::Ch0).unwrap(), 0.74);
+
+        hat.set_pwm_invert(Channel::Ch0, true);
+        hat.set_pwm_duty(Channel::Ch0, 0.0).unwrap();
+        assert_eq!(hat.get_pwm_duty(Channel::Ch0).unwrap(), 0.26);
+    }
+
+    #[test]
+    fn wrong_device_is_left_untouched() {
+        let mock = MockBus::new();
+        mock.regs.lock().unwrap()[Register::PID as usize] = 0x42;
+        mock.regs.lock().unwrap()[Register::PwmCtrl as usize] = 0x01;
+        let before = *mock.regs.lock().unwrap();
+
+        let result = DfrIoHat::connect(Box::new(mock.clone()), DfrIoHat::builder());
+
+        assert!(matches!(result, Err(DfrError::WrongDevice { pid: 0x42, .. })));
+        assert_eq!(*mock.regs.lock().unwrap(), before);
     }
 }
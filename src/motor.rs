@@ -0,0 +1,58 @@
+use std::io::Error as IoError;
+
+use crate::{Channel, DfrError, DfrIoHat};
+
+/// A DC motor driven both ways by two PWM channels through an external H-bridge, created by [`DfrIoHat::motor`].
+///
+/// The channels drive the two inputs of the bridge (e.g. `IN1` and `IN2` of an L298N or a DRV8833): the motor turns forward
+/// while only the first is driven, backward while only the second is, brakes with both high and coasts with both low.
+/// The PWM subsystem must be enabled.
+pub struct Motor<'a> {
+    hat: &'a mut DfrIoHat,
+    forward: Channel,
+    reverse: Channel,
+}
+
+impl DfrIoHat {
+    /// Drive a DC motor through an H-bridge wired to [`forward`] and [`reverse`], see [`Motor`].
+    /// Fails with [`DfrError::InvalidParameter`] if both are the same channel.
+    pub fn motor(&mut self, forward: Channel, reverse: Channel) -> Result<Motor<'_>, DfrError> {
+        if forward == reverse {
+            return Err(DfrError::InvalidParameter("a motor needs two different channels"));
+        }
+
+        Ok(Motor { hat: self, forward, reverse })
+    }
+}
+
+impl Motor<'_> {
+    /// Turn at [`speed`], between `-1.0` (full speed backward) and `1.0` (full speed forward), as the duty cycle of one channel.
+    /// The idle channel is zeroed first, so both inputs of the bridge are never driven at once.
+    pub fn set_speed(&mut self, speed: f32) -> Result<(), DfrError> {
+        if !(-1f32..=1f32).contains(&speed) {
+            return Err(DfrError::InvalidParameter("speed must be between -1.0 and 1.0"));
+        }
+
+        let (active, idle) = if speed < 0f32 {
+            (self.reverse, self.forward)
+        } else {
+            (self.forward, self.reverse)
+        };
+        self.hat.set_pwm_duty(idle, 0f32)?;
+        self.hat.set_pwm_duty(active, speed.abs())?;
+
+        Ok(())
+    }
+
+    /// Short the motor through the bridge, stopping it quickly, by driving both inputs high.
+    pub fn brake(&mut self) -> Result<(), IoError> {
+        self.hat.set_pwm_duty(self.forward, 1f32)?;
+        self.hat.set_pwm_duty(self.reverse, 1f32)
+    }
+
+    /// Let the motor spin down freely, by driving both inputs low.
+    pub fn coast(&mut self) -> Result<(), IoError> {
+        self.hat.set_pwm_duty(self.forward, 0f32)?;
+        self.hat.set_pwm_duty(self.reverse, 0f32)
+    }
+}
@@ -0,0 +1,170 @@
+use std::f32::consts::PI;
+use std::ops::RangeInclusive;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Channel, DfrError, DfrIoHat, CHANNEL_COUNT};
+
+/// Angle range hobby servos are driven over, in degrees.
+pub const SERVO_RANGE: f32 = 180.0;
+
+/// PWM frequency hobby servos expect, in hertz: a pulse every 20ms.
+pub const SERVO_FREQUENCY: u16 = 50;
+
+/// PWM frequencies analog servos tolerate without jittering, in hertz.
+const SERVO_TOLERATED: RangeInclusive<u16> = 40..=60;
+
+/// Pulse widths a servo expects at either end of its travel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServoCalibration {
+    /// Pulse width at `0°`, in microseconds.
+    pub min_us: u16,
+    /// Pulse width at [`SERVO_RANGE`], in microseconds.
+    pub max_us: u16,
+}
+
+/// Everything [`set_servo_angle`](DfrIoHat::set_servo_angle) needs to know about the servo on a channel,
+/// applied to all channels at once by [`DfrIoHat::configure_servos`], e.g. from a profile of a robot.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServoConfig {
+    pub calibration: ServoCalibration,
+    /// Degrees added to every angle, to correct a horn that isn't centered, see [`DfrIoHat::set_servo_trim`].
+    pub trim: f32,
+    /// See [`DfrIoHat::set_servo_deadband`].
+    pub deadband: f32,
+}
+
+impl Default for ServoCalibration {
+    /// The 1000µs to 2000µs range every hobby servo supports, if not its full travel.
+    fn default() -> Self {
+        ServoCalibration {
+            min_us: 1000,
+            max_us: 2000,
+        }
+    }
+}
+
+impl DfrIoHat {
+    /// Set the pulse widths of the servo on the specified channel, used by [`set_servo_angle`](Self::set_servo_angle).
+    pub fn set_servo_calibration(&mut self, channel: Channel, calibration: ServoCalibration) {
+        assert!(calibration.min_us < calibration.max_us);
+
+        self.servo_calibration[channel.index()] = calibration;
+    }
+
+    /// Snap angles within [`degrees`] of the center of travel to exactly the center in [`set_servo_angle`](Self::set_servo_angle),
+    /// to keep a servo driven by noisy input (e.g. a joystick) from jittering around its rest position. Defaults to `0`.
+    pub fn set_servo_deadband(&mut self, channel: Channel, degrees: f32) {
+        assert!(degrees >= 0f32);
+
+        self.servo_deadband[channel.index()] = degrees;
+    }
+
+    /// Offset every angle given to [`set_servo_angle`](Self::set_servo_angle) by [`degrees`], positive or negative,
+    /// to center a horn that's a spline off. Applied after the deadband, before clamping. Defaults to `0`.
+    pub fn set_servo_trim(&mut self, channel: Channel, degrees: f32) {
+        assert!(degrees.abs() <= SERVO_RANGE / 2.0);
+
+        self.servo_trim[channel.index()] = degrees;
+    }
+
+    /// Set the calibration, trim and deadband of the servos on all channels, indexed by channel.
+    /// Fails with [`DfrError::InvalidParameter`] without applying anything if any one of them is invalid:
+    /// pulses must widen from `min_us` to `max_us` and fit in a period at [`SERVO_FREQUENCY`], the trim must be within
+    /// half of [`SERVO_RANGE`] either way, and the deadband must not be negative.
+    pub fn configure_servos(&mut self, configs: [ServoConfig; CHANNEL_COUNT]) -> Result<(), DfrError> {
+        let period_us = 1_000_000 / SERVO_FREQUENCY as u32;
+
+        for config in &configs {
+            let ServoCalibration { min_us, max_us } = config.calibration;
+            if min_us >= max_us {
+                return Err(DfrError::InvalidParameter("min_us must be below max_us"));
+            }
+            if max_us as u32 > period_us {
+                return Err(DfrError::InvalidParameter("max_us must fit in a servo period"));
+            }
+            if !(-SERVO_RANGE / 2.0..=SERVO_RANGE / 2.0).contains(&config.trim) {
+                return Err(DfrError::InvalidParameter("trim must be within half the servo range"));
+            }
+            if config.deadband.is_nan() || config.deadband < 0f32 {
+                return Err(DfrError::InvalidParameter("deadband must not be negative"));
+            }
+        }
+
+        for (channel, config) in Channel::all().into_iter().zip(configs) {
+            self.set_servo_calibration(channel, config.calibration);
+            self.set_servo_trim(channel, config.trim);
+            self.set_servo_deadband(channel, config.deadband);
+        }
+
+        Ok(())
+    }
+
+    /// Drive the servo on the specified channel with pulses of [`us`] microseconds.
+    /// The pulse is quantized to the duty resolution, e.g. to 20µs steps at 50 Hz.
+    ///
+    /// Sets the frequency to [`SERVO_FREQUENCY`] first if none was set yet, see [`DfrIoHatBuilder::servo_auto_frequency`](crate::DfrIoHatBuilder::servo_auto_frequency).
+    /// From then on, with the `tracing` feature, setting a frequency servos don't tolerate emits a warning.
+    pub fn set_servo_us(&mut self, channel: Channel, us: u16) -> Result<(), DfrError> {
+        if !self.freq_configured && self.options.servo_auto_frequency && !self.options.minimal {
+            self.set_pwm_freq(SERVO_FREQUENCY)?;
+        }
+        self.servo_in_use = true;
+
+        let period_us = self.pwm_period_us()?;
+        if us as f32 > period_us {
+            return Err(DfrError::InvalidParameter("pulse must be shorter than the PWM period"));
+        }
+
+        let permille = (us as f32 * 1e3 / period_us).round();
+        self.set_pwm_duty_permille(channel, permille as u16)
+    }
+
+    /// Drive the servo on the specified channel to [`angle`] degrees, clamped to `0..=`[`SERVO_RANGE`],
+    /// according to its calibration, deadband and trim.
+    pub fn set_servo_angle(&mut self, channel: Channel, angle: f32) -> Result<(), DfrError> {
+        let i = channel.index();
+        let calibration = self.servo_calibration[i];
+
+        let center = SERVO_RANGE / 2.0;
+        let mut angle = angle.clamp(0.0, SERVO_RANGE);
+        if (angle - center).abs() <= self.servo_deadband[i] {
+            angle = center;
+        }
+        let angle = (angle + self.servo_trim[i]).clamp(0.0, SERVO_RANGE);
+
+        let span = (calibration.max_us - calibration.min_us) as f32;
+        let us = calibration.min_us as f32 + span * angle / SERVO_RANGE;
+        self.set_servo_us(channel, us.round() as u16)
+    }
+
+    /// Sweep the servo on the specified channel from [`from`] to [`to`] degrees and back, [`cycles`] times, each round trip
+    /// taking [`period`]. The angle follows a cosine, easing in and out at both ends, and is updated every 20ms, one frame at 50 Hz.
+    /// Angles go through [`set_servo_angle`](Self::set_servo_angle), so they're clamped and calibrated the same way. Blocks for the whole sweep.
+    pub fn sweep_servo(&mut self, channel: Channel, from: f32, to: f32, period: Duration, cycles: u32) -> Result<(), DfrError> {
+        let steps = (period.as_millis() / 20).max(1) as u32;
+        let interval = period / steps;
+
+        self.set_servo_angle(channel, from)?;
+        for _ in 0..cycles {
+            for step in 1..=steps {
+                thread::sleep(interval);
+
+                let progress = (1.0 - (2.0 * PI * step as f32 / steps as f32).cos()) / 2.0;
+                self.set_servo_angle(channel, from + (to - from) * progress)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Warn about a frequency servos don't tolerate, once a servo has been driven.
+    pub(crate) fn check_servo_frequency(&self, freq: u16) {
+        if self.servo_in_use && !SERVO_TOLERATED.contains(&freq) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(freq, "PWM frequency incompatible with the servos driven by the board, which expect {} Hz", SERVO_FREQUENCY);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::io::Error as IoError;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use crate::{Channel, DfrIoHat, CHANNEL_COUNT};
+
+/// Handle on a board shared between threads, cloned for each user.
+///
+/// Every method locks the board for the duration of the call, so calls from different threads never interleave,
+/// and long operations like [`fade_pwm`](Self::fade_pwm) hold the lock, blocking other users, until they complete.
+/// For anything not exposed here, or to run several calls without interleaving, use [`lock`](Self::lock).
+/// A panic while holding the lock doesn't poison the board for other users.
+#[derive(Clone)]
+pub struct SharedDfrIoHat(Arc<Mutex<DfrIoHat>>);
+
+impl SharedDfrIoHat {
+    pub fn new(hat: DfrIoHat) -> SharedDfrIoHat {
+        SharedDfrIoHat(Arc::new(Mutex::new(hat)))
+    }
+
+    /// Get exclusive access to the board until the guard is dropped.
+    pub fn lock(&self) -> MutexGuard<'_, DfrIoHat> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// See [`DfrIoHat::enable_pwm`].
+    pub fn enable_pwm(&self, enable: bool) -> Result<(), IoError> {
+        self.lock().enable_pwm(enable)
+    }
+
+    /// See [`DfrIoHat::enable_adc`].
+    pub fn enable_adc(&self, enable: bool) -> Result<(), IoError> {
+        self.lock().enable_adc(enable)
+    }
+
+    /// See [`DfrIoHat::set_pwm_duty`].
+    pub fn set_pwm_duty(&self, channel: Channel, duty: f32) -> Result<(), IoError> {
+        self.lock().set_pwm_duty(channel, duty)
+    }
+
+    /// See [`DfrIoHat::set_pwm_duties`].
+    pub fn set_pwm_duties(&self, duties: [f32; CHANNEL_COUNT]) -> Result<(), IoError> {
+        self.lock().set_pwm_duties(duties)
+    }
+
+    /// See [`DfrIoHat::get_pwm_duty`].
+    pub fn get_pwm_duty(&self, channel: Channel) -> Result<f32, IoError> {
+        self.lock().get_pwm_duty(channel)
+    }
+
+    /// See [`DfrIoHat::set_pwm_freq`].
+    pub fn set_pwm_freq(&self, freq: u16) -> Result<(), IoError> {
+        self.lock().set_pwm_freq(freq)
+    }
+
+    /// See [`DfrIoHat::fade_pwm`]. Holds the lock for the whole fade.
+    pub fn fade_pwm(&self, channel: Channel, from: f32, to: f32, duration: Duration, steps: u32) -> Result<(), IoError> {
+        self.lock().fade_pwm(channel, from, to, duration, steps)
+    }
+
+    /// See [`DfrIoHat::all_off`].
+    pub fn all_off(&self) -> Result<(), IoError> {
+        self.lock().all_off()
+    }
+
+    /// See [`DfrIoHat::get_adc_value`].
+    pub fn get_adc_value(&self, channel: Channel) -> Result<u16, IoError> {
+        self.lock().get_adc_value(channel)
+    }
+
+    /// See [`DfrIoHat::get_adc_values`].
+    pub fn get_adc_values(&self) -> Result<[u16; CHANNEL_COUNT], IoError> {
+        self.lock().get_adc_values()
+    }
+
+    /// See [`DfrIoHat::feed_watchdog`].
+    pub fn feed_watchdog(&self) {
+        self.lock().feed_watchdog()
+    }
+}
+
+impl From<DfrIoHat> for SharedDfrIoHat {
+    fn from(hat: DfrIoHat) -> Self {
+        SharedDfrIoHat::new(hat)
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::Error as IoError;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Channel, DfrError, DfrIoHat, CHANNEL_COUNT};
+
+/// Half-step energization pattern of four coil phases, in wiring order. Full steps are the odd entries, two phases on at a time.
+const HALF_STEPS: [[bool; 4]; 8] = [
+    [true, false, false, false],
+    [true, true, false, false],
+    [false, true, false, false],
+    [false, true, true, false],
+    [false, false, true, false],
+    [false, false, true, true],
+    [false, false, false, true],
+    [true, false, false, true],
+];
+
+/// Full-step pattern of the two control lines of an H-bridge driver, as in the two-wire sequence of the Arduino `Stepper` library.
+const TWO_WIRE_STEPS: [[bool; 2]; 4] = [
+    [false, true],
+    [true, true],
+    [true, false],
+    [false, false],
+];
+
+/// Sequence a [`Stepper`] energizes its coils with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StepMode {
+    /// Two phases on at a time, for the most torque.
+    #[default]
+    Full,
+    /// Alternating between one and two phases on, for twice the resolution. Needs four channels.
+    Half,
+}
+
+/// Direction of a single step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// A small stepper motor driven by two or four PWM channels, created by [`DfrIoHat::stepper`].
+///
+/// With four channels, they drive the four coil phases in order (`A`, `B`, `A'`, `B'`), e.g. the `IN1` to `IN4`
+/// inputs of a ULN2003 board for a 28BYJ-48. With two, they drive the two control lines of an H-bridge driver,
+/// which only supports [`StepMode::Full`]. Coils are energized at a duty cycle of `1.0` at the current frequency,
+/// so the PWM subsystem must be enabled.
+pub struct Stepper<'a> {
+    hat: &'a mut DfrIoHat,
+    coils: Vec<Channel>,
+    mode: StepMode,
+    position: usize,
+}
+
+impl DfrIoHat {
+    /// Drive a stepper motor wired to [`coils`], two or four channels, see [`Stepper`].
+    /// Fails with [`DfrError::InvalidParameter`] for any other number of channels, or for half steps on two channels.
+    pub fn stepper(&mut self, coils: &[Channel], mode: StepMode) -> Result<Stepper<'_>, DfrError> {
+        match (coils.len(), mode) {
+            (CHANNEL_COUNT, _) | (2, StepMode::Full) => {}
+            (2, StepMode::Half) => return Err(DfrError::InvalidParameter("half steps need four channels")),
+            _ => return Err(DfrError::InvalidParameter("a stepper needs two or four channels")),
+        }
+
+        Ok(Stepper {
+            hat: self,
+            coils: coils.to_vec(),
+            mode,
+            position: 0,
+        })
+    }
+}
+
+impl Stepper<'_> {
+    /// Advance the energization pattern by one step in the given direction.
+    pub fn step(&mut self, dir: Direction) -> Result<(), IoError> {
+        let len = self.sequence_len();
+        self.position = match dir {
+            Direction::Forward => (self.position + 1) % len,
+            Direction::Backward => (self.position + len - 1) % len,
+        };
+
+        self.energize()
+    }
+
+    /// Take [`count`] steps, forward if positive and backward if negative, waiting [`delay`] after each one.
+    pub fn steps(&mut self, count: i32, delay: Duration) -> Result<(), IoError> {
+        let dir = if count < 0 { Direction::Backward } else { Direction::Forward };
+
+        for _ in 0..count.unsigned_abs() {
+            self.step(dir)?;
+            thread::sleep(delay);
+        }
+
+        Ok(())
+    }
+
+    /// De-energize all the coils, letting the motor spin freely and stop drawing current.
+    pub fn release(&mut self) -> Result<(), IoError> {
+        for &coil in &self.coils {
+            self.hat.set_pwm_duty(coil, 0f32)?;
+        }
+
+        Ok(())
+    }
+
+    fn sequence_len(&self) -> usize {
+        match (self.coils.len(), self.mode) {
+            (2, _) => TWO_WIRE_STEPS.len(),
+            (_, StepMode::Full) => HALF_STEPS.len() / 2,
+            (_, StepMode::Half) => HALF_STEPS.len(),
+        }
+    }
+
+    fn energize(&mut self) -> Result<(), IoError> {
+        let pattern: &[bool] = match (self.coils.len(), self.mode) {
+            (2, _) => &TWO_WIRE_STEPS[self.position],
+            (_, StepMode::Full) => &HALF_STEPS[self.position * 2 + 1],
+            (_, StepMode::Half) => &HALF_STEPS[self.position],
+        };
+
+        for (&coil, &on) in self.coils.iter().zip(pattern) {
+            self.hat.set_pwm_duty(coil, if on { 1f32 } else { 0f32 })?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use std::io::Error as IoError;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use futures_core::Stream;
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::{Channel, DfrIoHat};
+
+/// Stream of readings of one ADC pin, returned by [`DfrIoHat::adc_stream`].
+pub(crate) struct AdcStream {
+    hat: DfrIoHat,
+    channel: Channel,
+    interval: Interval,
+}
+
+impl AdcStream {
+    pub(crate) fn new(hat: DfrIoHat, channel: Channel, period: Duration) -> AdcStream {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        AdcStream {
+            hat,
+            channel,
+            interval,
+        }
+    }
+}
+
+impl Stream for AdcStream {
+    type Item = Result<u16, IoError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.interval.poll_tick(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        Poll::Ready(Some(this.hat.get_adc_value(this.channel)))
+    }
+}
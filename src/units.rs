@@ -0,0 +1,65 @@
+use std::io::Error as IoError;
+
+use crate::{Channel, DfrError, DfrIoHat, FREQ_RANGE};
+
+/// A PWM duty cycle, validated to be between `0.000` and `1.000` once, when it's constructed.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Duty(f32);
+
+impl Duty {
+    /// Fails with [`DfrError::InvalidDuty`] if [`duty`] isn't between `0.000` and `1.000`.
+    pub fn new(duty: f32) -> Result<Duty, DfrError> {
+        if !(0f32..=1f32).contains(&duty) {
+            return Err(DfrError::InvalidDuty(duty));
+        }
+
+        Ok(Duty(duty))
+    }
+
+    pub fn get(self) -> f32 {
+        self.0
+    }
+}
+
+impl From<Duty> for f32 {
+    fn from(duty: Duty) -> Self {
+        duty.0
+    }
+}
+
+/// A PWM frequency in hertz, validated to be within [`FREQ_RANGE`] once, when it's constructed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Freq(u16);
+
+impl Freq {
+    /// Fails with [`DfrError::InvalidFrequency`] if [`freq`] isn't within [`FREQ_RANGE`].
+    pub fn new(freq: u16) -> Result<Freq, DfrError> {
+        if !FREQ_RANGE.contains(&freq) {
+            return Err(DfrError::InvalidFrequency(freq));
+        }
+
+        Ok(Freq(freq))
+    }
+
+    pub fn get(self) -> u16 {
+        self.0
+    }
+}
+
+impl From<Freq> for u16 {
+    fn from(freq: Freq) -> Self {
+        freq.0
+    }
+}
+
+impl DfrIoHat {
+    /// Same as [`set_pwm_duty`](Self::set_pwm_duty), with a duty cycle that's already known to be in range.
+    pub fn set_duty(&mut self, channel: Channel, duty: Duty) -> Result<(), IoError> {
+        self.set_pwm_duty(channel, duty.get())
+    }
+
+    /// Same as [`set_pwm_freq`](Self::set_pwm_freq), with a frequency that's already known to be in range.
+    pub fn set_freq(&mut self, freq: Freq) -> Result<(), IoError> {
+        self.set_pwm_freq(freq.get())
+    }
+}
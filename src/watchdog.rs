@@ -0,0 +1,86 @@
+use std::io::Error as IoError;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::bus::BusHandle;
+use crate::{lock_bus, outputs_off};
+
+/// Software watchdog that cuts all PWM outputs if it isn't fed within its timeout.
+/// See [`DfrIoHat::start_watchdog`](crate::DfrIoHat::start_watchdog).
+pub(crate) struct Watchdog {
+    state: Arc<(Mutex<State>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct State {
+    last_fed: Instant,
+    stopped: bool,
+    tripped: bool,
+}
+
+impl Watchdog {
+    pub(crate) fn start(dev: Arc<Mutex<BusHandle>>, timeout: Duration) -> Result<Watchdog, IoError> {
+        let state = Arc::new((Mutex::new(State {
+            last_fed: Instant::now(),
+            stopped: false,
+            tripped: false,
+        }), Condvar::new()));
+
+        let thread_state = state.clone();
+        let thread = thread::Builder::new()
+            .name("dfr-io-hat-watchdog".to_string())
+            .spawn(move || Self::run(dev, thread_state, timeout))?;
+
+        Ok(Watchdog {
+            state,
+            thread: Some(thread),
+        })
+    }
+
+    fn run(dev: Arc<Mutex<BusHandle>>, state: Arc<(Mutex<State>, Condvar)>, timeout: Duration) {
+        let (lock, cvar) = &*state;
+        let mut guard = lock.lock().unwrap();
+
+        loop {
+            if guard.stopped {
+                return;
+            }
+
+            let now = Instant::now();
+            let deadline = guard.last_fed + timeout;
+            if now >= deadline {
+                break;
+            }
+
+            guard = cvar.wait_timeout(guard, deadline - now).unwrap().0;
+        }
+
+        guard.tripped = true;
+        drop(guard);
+
+        // Best-effort: there's nobody left to report a failure to.
+        let _ = outputs_off(&mut **lock_bus(&dev));
+    }
+
+    /// Reset the timer.
+    pub(crate) fn feed(&self) {
+        self.state.0.lock().unwrap().last_fed = Instant::now();
+    }
+
+    pub(crate) fn tripped(&self) -> bool {
+        self.state.0.lock().unwrap().tripped
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.state;
+        lock.lock().unwrap().stopped = true;
+        cvar.notify_all();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}